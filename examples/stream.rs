@@ -0,0 +1,103 @@
+//! Using the crate as a library
+//!
+//! Connects to a scanner, enables Code 128 and EAN-13, beeps once and then
+//! prints every scan as a line of JSON.
+//!
+//! ```sh
+//! cargo run --example stream -- /dev/ttyACM0 9600
+//! ```
+
+use std::fmt::Write;
+use std::process::exit;
+
+use ssi::{
+    BeepCode, CodeId, Dispatcher, OpCode, ParamNumber, Scanner, Source,
+    SsiConfig,
+};
+
+const ENABLE_CODE128: (ParamNumber, u8) = (ParamNumber(0x08), 0x01);
+const ENABLE_EAN13: (ParamNumber, u8) = (ParamNumber(0x03), 0x01);
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(port), baud) = (args.next(), args.next()) else {
+        eprintln!("Usage: stream <port> [baud]");
+        exit(1);
+    };
+    let baud = baud
+        .map_or(Ok(9600), |baud| baud.parse())
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid baud rate: {e}");
+            exit(1);
+        });
+
+    let mut scanner = match Scanner::open(&port, baud, SsiConfig::default()) {
+        Ok(scanner) => scanner,
+        Err(e) => {
+            eprintln!("Failed to open \"{port}\". Error: {e}");
+            exit(1);
+        }
+    };
+
+    // Temporary, the scanner is back to its stored settings after a power
+    // cycle
+    let host = u8::from(Source::Host);
+    if let Err(e) = scanner
+        .set_params(&[ENABLE_CODE128, ENABLE_EAN13], false)
+        .and_then(|()| {
+            scanner.send(&[
+                OpCode::Beep.into(),
+                host,
+                0x00,
+                BeepCode::OneShortHigh as u8,
+            ])
+        })
+    {
+        eprintln!("Error: {e}");
+        exit(1);
+    }
+
+    let mut dispatcher = Dispatcher::new();
+    dispatcher
+        .on(OpCode::DecodeData, |message| {
+            let Some(scan) = message.scan_data(CodeId::None) else {
+                return;
+            };
+            let symbology = match scan.symbology() {
+                Some(content_type) => format!("{content_type:?}"),
+                None => format!("{:#04x}", scan.content_type),
+            };
+
+            println!(
+                r#"{{"symbology": "{}", "data": "{}"}}"#,
+                json_escape(&symbology),
+                json_escape(&String::from_utf8_lossy(scan.content))
+            );
+        })
+        .on_error(|e| eprintln!("Error: {e}"));
+
+    if let Err(e) = scanner.dispatch(&mut dispatcher) {
+        eprintln!("Error: {e}");
+        exit(1);
+    }
+}
+
+fn json_escape(val: &str) -> String {
+    let mut escaped = String::with_capacity(val.len());
+    for c in val.chars() {
+        match c {
+            '"' => escaped.push_str(r#"\""#),
+            '\\' => escaped.push_str(r"\\"),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            '\t' => escaped.push_str(r"\t"),
+            // Writing to a String can't fail
+            c if c.is_control() => {
+                write!(escaped, "\\u{:04x}", u32::from(c)).unwrap()
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
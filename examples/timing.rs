@@ -0,0 +1,129 @@
+//! Rough timings of the hot paths, for comparing a change against the
+//! commit before it
+//!
+//! ```sh
+//! cargo run --release --example timing
+//! ```
+//!
+//! Every case runs for half a second and prints the mean time per
+//! iteration. Decoding verifies the checksum on the way, which is all of
+//! its work that grows with the frame, so the decode rows double as
+//! checksum timings.
+
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::io::Write as _;
+use std::time::{Duration, Instant};
+
+use ssi::{
+    decode, wrap, wrap_into, CodeId, Framer, OpCode, ScanData, Source, Status,
+    TextEncoding,
+};
+
+/// How long each case runs
+const RUN: Duration = Duration::from_millis(500);
+/// Data bytes in a frame of the maximum length
+const MAX_DATA: usize = 251;
+
+/// Calls `f` for `RUN` and prints the mean time per call
+fn time(name: &str, mut f: impl FnMut()) {
+    // Warms up caches and branch prediction
+    for _ in 0..100 {
+        f();
+    }
+
+    let start = Instant::now();
+    let mut calls = 0;
+    while start.elapsed() < RUN {
+        for _ in 0..100 {
+            f();
+        }
+        calls += 100;
+    }
+
+    println!("{name:<36} {:>12.2?}", start.elapsed() / calls);
+}
+
+/// A scanner frame carrying `data`
+fn frame(opcode: OpCode, status: Status, data: &[u8]) -> Vec<u8> {
+    let mut payload =
+        vec![opcode.into(), Source::Scanner.into(), status.into()];
+    payload.extend_from_slice(data);
+    wrap(payload).unwrap()
+}
+
+/// Pushes `stream` through a `Framer` the way a port delivers it, in reads
+/// of up to 256 bytes, and takes all frames off
+fn frame_stream(stream: &[u8]) {
+    let mut framer = Framer::default();
+    for chunk in stream.chunks(256) {
+        framer.push(chunk);
+        while let Some(frame) = framer.next_frame() {
+            black_box(frame.unwrap());
+        }
+    }
+}
+
+fn main() {
+    let ean13 =
+        frame(OpCode::DecodeData, Status::default(), b"\x0b4006381333931");
+    let pdf417 = frame(
+        OpCode::DecodeData,
+        Status::Continuation,
+        &[&[0x11][..], &[b'P'; MAX_DATA - 1]].concat(),
+    );
+    let image =
+        frame(OpCode::ImageData, Status::Continuation, &[0xa5; MAX_DATA]);
+
+    time("decode, EAN-13", || {
+        black_box(decode(black_box(&ean13)).unwrap());
+    });
+    time("decode, PDF417 packet", || {
+        black_box(decode(black_box(&pdf417)).unwrap());
+    });
+    time("decode, image packet", || {
+        black_box(decode(black_box(&image)).unwrap());
+    });
+
+    // What an ACK costs with and without allocating the frame
+    let ack = [OpCode::Ack.into(), Source::Host.into(), 0x00];
+    time("wrap, ACK", || {
+        black_box(wrap(black_box(ack.to_vec())).unwrap());
+    });
+    let mut buf = Vec::new();
+    time("wrap_into, ACK", || {
+        wrap_into(&mut buf, black_box(&ack)).unwrap();
+        black_box(&buf);
+    });
+
+    // What `SsiConfig::quiet_decode` saves per scan, printing aside
+    let data = decode(&ean13).unwrap().data;
+    let mut line = String::new();
+    time("scan, described", || {
+        line.clear();
+        let scan = ScanData::parse(black_box(data), CodeId::None).unwrap();
+        if let Some(content_type) = scan.symbology() {
+            write!(line, "{content_type} {:?}", content_type.aim_id()).unwrap();
+        }
+        let decoded = TextEncoding::Utf8.decode(scan.content).unwrap();
+        write!(line, "{decoded}").unwrap();
+        black_box(&line);
+    });
+    let mut raw = Vec::new();
+    time("scan, quiet", || {
+        raw.clear();
+        write!(raw, "{:?}", OpCode::DecodeData).unwrap();
+        raw.extend_from_slice(black_box(data));
+        black_box(&raw);
+    });
+
+    // Per frame overhead dominates with many small frames
+    let scans = ean13.repeat(1000);
+    time("framer, 1000 EAN-13 frames", || {
+        frame_stream(black_box(&scans))
+    });
+    let packets = image.repeat(100);
+    time("framer, 100 image packets", || {
+        frame_stream(black_box(&packets))
+    });
+}
@@ -0,0 +1,77 @@
+use crate::{OpCode, RawMessage, Source, Status};
+
+/// A message put back together from its packets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompleteMessage {
+    pub opcode: OpCode,
+    pub source: Source,
+    /// Data of all packets, with a single content type byte for
+    /// `DecodeData`
+    pub data: Vec<u8>,
+    pub packets: usize,
+}
+
+/// Reassembles multipacket messages
+///
+/// Messages too long for a single frame are split into packets with the
+/// same opcode, all but the last one with `Status::Continuation` set. For
+/// `DecodeData`, every packet repeats the content type byte.
+///
+/// A packet with another opcode in the middle of a multipacket message
+/// means the rest of it got lost, the packets collected so far are
+/// discarded. ACKs and NACKs don't interrupt, they belong to the
+/// handshake.
+#[derive(Debug, Default)]
+pub struct MessageAssembler {
+    pending: Option<CompleteMessage>,
+}
+
+impl MessageAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a packet, returning the message once it is complete
+    ///
+    /// Single packet messages are returned right away.
+    pub fn push(&mut self, message: &RawMessage) -> Option<CompleteMessage> {
+        if let OpCode::Ack | OpCode::Nack = message.opcode {
+            return Some(CompleteMessage {
+                opcode: message.opcode,
+                source: message.source,
+                data: message.data.to_vec(),
+                packets: 1,
+            });
+        }
+
+        match &mut self.pending {
+            Some(pending) if pending.opcode == message.opcode => {
+                let data = match (message.opcode, message.data) {
+                    (OpCode::DecodeData, [_, data @ ..]) => data,
+                    (_, data) => data,
+                };
+                pending.data.extend_from_slice(data);
+                pending.packets += 1;
+            }
+            _ => {
+                self.pending = Some(CompleteMessage {
+                    opcode: message.opcode,
+                    source: message.source,
+                    data: message.data.to_vec(),
+                    packets: 1,
+                });
+            }
+        }
+
+        if message.status.contains(Status::Continuation) {
+            return None;
+        }
+
+        self.pending.take()
+    }
+
+    /// Whether a multipacket message is waiting for further packets
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use serialport::FlowControl;
+
+use crate::{
+    ChecksumFormat, ChecksumPolicy, CodeId, ContentType, Scanner, Source,
+    SsiConfig, SsiError, TextEncoding,
+};
+
+/// Sets up a connection to a scanner, see `Scanner::builder`
+///
+/// Every setting left alone keeps the default documented on its setter.
+#[derive(Debug, Clone)]
+pub struct ScannerBuilder {
+    port_name: String,
+    baud_rate: u32,
+    flow_control: FlowControl,
+    symbologies: Option<Vec<ContentType>>,
+    config: SsiConfig,
+}
+
+impl ScannerBuilder {
+    pub fn new(port_name: impl Into<String>) -> Self {
+        ScannerBuilder {
+            port_name: port_name.into(),
+            baud_rate: 9600,
+            flow_control: FlowControl::None,
+            symbologies: None,
+            config: SsiConfig::default(),
+        }
+    }
+
+    /// Defaults to 9600, the scanners' factory setting
+    pub fn baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    /// Defaults to no flow control
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// Replaces all settings below at once, starting from a template
+    pub fn config(mut self, config: SsiConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See `SsiConfig::read_timeout`, 10 ms by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = timeout;
+        self
+    }
+
+    /// Defaults to big endian and negated, as specified
+    pub fn checksum(mut self, checksum: ChecksumFormat) -> Self {
+        self.config.checksum = checksum;
+        self
+    }
+
+    /// Defaults to `ChecksumPolicy::Strict`
+    pub fn checksum_policy(mut self, policy: ChecksumPolicy) -> Self {
+        self.config.checksum_policy = policy;
+        self
+    }
+
+    /// See `SsiConfig::validate_frames`, off by default
+    pub fn validate_frames(mut self, validate: bool) -> Self {
+        self.config.validate_frames = validate;
+        self
+    }
+
+    /// See `SsiConfig::auto_ack`, on by default
+    pub fn auto_ack(mut self, auto_ack: bool) -> Self {
+        self.config.auto_ack = auto_ack;
+        self
+    }
+
+    /// Defaults to `Source::Host`
+    pub fn ack_source(mut self, source: Source) -> Self {
+        self.config.ack_source = source;
+        self
+    }
+
+    /// Defaults to `CodeId::None`
+    pub fn code_id(mut self, code_id: CodeId) -> Self {
+        self.config.code_id = code_id;
+        self
+    }
+
+    /// See `SsiConfig::encoding`, defaults to `TextEncoding::Utf8`
+    pub fn encoding(mut self, encoding: TextEncoding) -> Self {
+        self.config.encoding = encoding;
+        self
+    }
+
+    /// Leaves only `types` enabled on connecting, until the scanner is
+    /// powered off, see `Scanner::enable_only`
+    ///
+    /// By default the symbologies are left as the scanner has them.
+    pub fn symbologies(mut self, types: &[ContentType]) -> Self {
+        self.symbologies = Some(types.to_vec());
+        self
+    }
+
+    /// See `SsiConfig::command_delay`, zero by default
+    pub fn command_delay(mut self, delay: Duration) -> Self {
+        self.config.command_delay = delay;
+        self
+    }
+
+    /// Opens the serial port
+    ///
+    /// Fails if a symbology given to `symbologies` has no known parameter,
+    /// or the scanner doesn't take the change.
+    pub fn connect(self) -> Result<Scanner, SsiError> {
+        let port = serialport::new(self.port_name, self.baud_rate)
+            .timeout(self.config.read_timeout)
+            .flow_control(self.flow_control)
+            .open()?;
+
+        let mut scanner = Scanner::new(port, self.config);
+        if let Some(types) = self.symbologies {
+            scanner.enable_only(&types, false)?;
+        }
+
+        Ok(scanner)
+    }
+}
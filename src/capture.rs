@@ -0,0 +1,169 @@
+//! Raw frame capture for reproducing problems in the field
+//!
+//! A capture file is a sequence of records, one per frame: a direction byte
+//! (0 for scanner to host, 1 for host to scanner), the frame length as big
+//! endian u16 and the frame bytes as they went over the line.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Direction;
+
+/// Writes frames to a capture file
+///
+/// Once the file would grow beyond `max_size`, it is moved aside to the
+/// same name with `.1` appended, replacing an earlier one, and a new file
+/// is started. The capture takes up at most twice `max_size` that way.
+pub struct Capture {
+    path: PathBuf,
+    file: BufWriter<File>,
+    written: u64,
+    max_size: u64,
+}
+
+impl Capture {
+    pub fn create(path: impl Into<PathBuf>, max_size: u64) -> io::Result<Self> {
+        let path = path.into();
+        let file = BufWriter::new(File::create(&path)?);
+
+        Ok(Capture {
+            path,
+            file,
+            written: 0,
+            max_size,
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        frame: &[u8],
+    ) -> io::Result<()> {
+        let len = u16::try_from(frame.len())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let record_len = 3 + u64::from(len);
+        if self.written > 0 && self.written + record_len > self.max_size {
+            self.rotate()?;
+        }
+
+        let direction = match direction {
+            Direction::ScannerToHost => 0,
+            Direction::HostToScanner => 1,
+        };
+        self.file.write_all(&[direction])?;
+        self.file.write_all(&len.to_be_bytes())?;
+        self.file.write_all(frame)?;
+        // Flushed right away, the capture is most useful when things crash
+        self.file.flush()?;
+        self.written += record_len;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        fs::rename(&self.path, rotated)?;
+
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.written = 0;
+
+        Ok(())
+    }
+}
+
+/// Reads back a capture file, for `transcript` or replaying it
+pub fn read_capture(path: &Path) -> io::Result<Vec<(Direction, Vec<u8>)>> {
+    let data = fs::read(path)?;
+    let mut frames = Vec::new();
+    let mut rest = &data[..];
+
+    while !rest.is_empty() {
+        let [direction, len1, len2, tail @ ..] = rest else {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        };
+        let direction = match direction {
+            0 => Direction::ScannerToHost,
+            1 => Direction::HostToScanner,
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        let len = usize::from(u16::from_be_bytes([*len1, *len2]));
+        if tail.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let (frame, tail) = tail.split_at(len);
+        frames.push((direction, frame.to_vec()));
+        rest = tail;
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for the files of test `name`
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("ssi-capture-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = test_dir("round_trip");
+        let path = dir.join("capture");
+        let mut capture = Capture::create(&path, 1024).unwrap();
+        capture
+            .record(Direction::HostToScanner, &[0x04, 0xe4, 0x04, 0x00])
+            .unwrap();
+        capture.record(Direction::ScannerToHost, &[0x04]).unwrap();
+
+        let frames = read_capture(&path).unwrap();
+        assert_eq!(
+            frames,
+            [
+                (Direction::HostToScanner, vec![0x04, 0xe4, 0x04, 0x00]),
+                (Direction::ScannerToHost, vec![0x04]),
+            ]
+        );
+
+        // A record cut short is an error rather than a frame
+        let mut data = fs::read(&path).unwrap();
+        data.pop();
+        fs::write(&path, data).unwrap();
+        assert!(read_capture(&path).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = test_dir("rotation");
+        let path = dir.join("capture");
+        // Room for two records of 3 + 5 bytes
+        let mut capture = Capture::create(&path, 16).unwrap();
+        for byte in 0..3 {
+            capture
+                .record(Direction::ScannerToHost, &[byte; 5])
+                .unwrap();
+        }
+
+        let rotated = read_capture(&dir.join("capture.1")).unwrap();
+        assert_eq!(
+            rotated,
+            [
+                (Direction::ScannerToHost, vec![0; 5]),
+                (Direction::ScannerToHost, vec![1; 5]),
+            ]
+        );
+        let current = read_capture(&path).unwrap();
+        assert_eq!(current, [(Direction::ScannerToHost, vec![2; 5])]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+/// Byte order of the two checksum bytes at the end of a frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
+}
+
+/// How the checksum is laid out on the wire
+///
+/// Standard SSI transmits the two's complement of the 16 bit sum, high
+/// byte first. Some related scanner families deviate from that in byte order
+/// or by sending the plain sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumFormat {
+    pub byte_order: ByteOrder,
+    /// Whether the two's complement of the sum is sent instead of the sum
+    pub negated: bool,
+}
+
+impl Default for ChecksumFormat {
+    fn default() -> ChecksumFormat {
+        ChecksumFormat {
+            byte_order: ByteOrder::BigEndian,
+            negated: true,
+        }
+    }
+}
+
+impl ChecksumFormat {
+    /// Every supported convention, the standard one first
+    pub const ALL: [ChecksumFormat; 4] = [
+        ChecksumFormat {
+            byte_order: ByteOrder::BigEndian,
+            negated: true,
+        },
+        ChecksumFormat {
+            byte_order: ByteOrder::LittleEndian,
+            negated: true,
+        },
+        ChecksumFormat {
+            byte_order: ByteOrder::BigEndian,
+            negated: false,
+        },
+        ChecksumFormat {
+            byte_order: ByteOrder::LittleEndian,
+            negated: false,
+        },
+    ];
+
+    pub(crate) fn encode(self, sum: u16) -> [u8; 2] {
+        let checksum = if self.negated {
+            sum.wrapping_neg()
+        } else {
+            sum
+        };
+
+        match self.byte_order {
+            ByteOrder::BigEndian => checksum.to_be_bytes(),
+            ByteOrder::LittleEndian => checksum.to_le_bytes(),
+        }
+    }
+
+    /// Reads the checksum bytes of a frame as a number
+    pub(crate) fn read(self, checksum: [u8; 2]) -> u16 {
+        match self.byte_order {
+            ByteOrder::BigEndian => u16::from_be_bytes(checksum),
+            ByteOrder::LittleEndian => u16::from_le_bytes(checksum),
+        }
+    }
+
+    pub(crate) fn matches(self, sum: u16, checksum: [u8; 2]) -> bool {
+        self.encode(sum) == checksum
+    }
+}
+
+/// What to do with frames whose checksum doesn't match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    /// Reject them with `DecodeError::InvalidChecksum`
+    #[default]
+    Strict,
+    /// Decode them anyway, with `checksum_failed` set on the message. Meant
+    /// for recovering what's left from a flaky line, not for production.
+    BestEffort,
+}
+
+/// The 16 bit sum of the length byte and the payload, before negation
+///
+/// A frame can't hold enough bytes for the sum to overflow, but wrapping
+/// keeps arbitrary input from panicking in debug builds.
+pub(crate) fn calc_checksum(size: u8, payload: &[u8]) -> u16 {
+    payload.iter().fold(u16::from(size), |sum, byte| {
+        sum.wrapping_add(u16::from(*byte))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, wrap};
+
+    #[test]
+    fn sum_above_i16_max() {
+        // 0xcc + 0xab + 0x04 + 200 * 0xff = 0xc8b3
+        let mut payload = vec![0xab, 0x04, 0x00];
+        payload.extend([0xff; 200]);
+        assert_eq!(calc_checksum(0xcc, &payload), 0xc8b3);
+
+        let frame = wrap(payload).unwrap();
+        assert_eq!(frame[frame.len() - 2..], [0x37, 0x4d]);
+        assert_eq!(decode(&frame).unwrap().data, [0xff; 200]);
+    }
+
+    #[test]
+    fn formats() {
+        let sum = 0x1234;
+        let [standard, little_endian, plain, plain_little_endian] =
+            ChecksumFormat::ALL.map(|format| format.encode(sum));
+        assert_eq!(standard, [0xed, 0xcc]);
+        assert_eq!(little_endian, [0xcc, 0xed]);
+        assert_eq!(plain, [0x12, 0x34]);
+        assert_eq!(plain_little_endian, [0x34, 0x12]);
+    }
+}
@@ -0,0 +1,464 @@
+//! Complete frames for the host's commands, ready to be written to the port
+
+use std::time::Duration;
+
+use crate::param::{
+    change_all_code_types_payload, enable_params, param_request_payload,
+    param_send_payload,
+};
+use crate::{wrap, ContentType, OpCode, ParamNumber, Source, SsiError, Status};
+
+/// A frame to be sent, for commands without a builder of their own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub opcode: OpCode,
+    pub source: Source,
+    pub status: Status,
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    /// The complete frame, with length byte and checksum
+    ///
+    /// Fails if `data` doesn't fit a frame, see `MAX_PAYLOAD`.
+    pub fn encode(self) -> Result<Vec<u8>, SsiError> {
+        let mut payload =
+            vec![self.opcode.into(), self.source.into(), self.status.into()];
+        payload.extend(self.data);
+
+        wrap(payload)
+    }
+}
+
+/// Frames a payload of a few bytes, which always fits
+fn small_frame(payload: Vec<u8>) -> Vec<u8> {
+    wrap(payload).expect("a few bytes fit a frame")
+}
+
+fn command(opcode: OpCode, data: &[u8]) -> Vec<u8> {
+    let mut payload =
+        vec![opcode.into(), Source::Host.into(), Status::default().into()];
+    payload.extend_from_slice(data);
+
+    small_frame(payload)
+}
+
+/// Payload of an ACK carrying `source` and `status`
+pub(crate) fn ack_payload(source: Source, status: Status) -> [u8; 3] {
+    [OpCode::Ack.into(), source.into(), status.into()]
+}
+
+/// Payload of a NACK from `source` giving `reason`
+pub(crate) fn nack_payload(source: Source, reason: NackReason) -> [u8; 4] {
+    [
+        OpCode::Nack.into(),
+        source.into(),
+        Status::default().into(),
+        reason.into(),
+    ]
+}
+
+/// Acknowledges a scanner frame
+///
+/// `source` is normally `Source::Host`, see `SsiConfig::ack_source`.
+/// `status` should echo the `Continuation` bit of the frame acknowledged,
+/// firmware checking it takes the ACK for the whole message otherwise.
+pub fn ack(source: Source, status: Status) -> Vec<u8> {
+    small_frame(ack_payload(source, status).to_vec())
+}
+
+/// Rejects a scanner frame, asking for it again for `NackReason::Resend`
+pub fn nack(reason: NackReason) -> Vec<u8> {
+    small_frame(nack_payload(Source::Host, reason).to_vec())
+}
+
+/// Why a frame got NACKed, given by the NACK's data byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NackReason {
+    /// Bad checksum or length, the frame is to be sent again
+    Resend,
+    /// The frame makes no sense at this point, like an unknown opcode or a
+    /// reply without a request
+    BadContext,
+    /// The frame is fine, but what it asks for isn't possible, like a
+    /// parameter value out of range
+    Denied,
+    Other(u8),
+}
+
+impl From<u8> for NackReason {
+    fn from(val: u8) -> Self {
+        match val {
+            0x01 => NackReason::Resend,
+            0x02 => NackReason::BadContext,
+            0x06 => NackReason::Denied,
+            _ => NackReason::Other(val),
+        }
+    }
+}
+
+impl From<NackReason> for u8 {
+    fn from(val: NackReason) -> Self {
+        match val {
+            NackReason::Resend => 0x01,
+            NackReason::BadContext => 0x02,
+            NackReason::Denied => 0x06,
+            NackReason::Other(val) => val,
+        }
+    }
+}
+
+/// Allows the scanner to scan, undoing `scan_disable`
+pub fn scan_enable() -> Vec<u8> {
+    command(OpCode::ScanEnable, &[])
+}
+
+/// Keeps the scanner from scanning until `scan_enable`
+pub fn scan_disable() -> Vec<u8> {
+    command(OpCode::ScanDisable, &[])
+}
+
+/// Scans as if the trigger was pulled, until a label is decoded, the
+/// session times out or `stop_session`
+pub fn start_session() -> Vec<u8> {
+    command(OpCode::StartSession, &[])
+}
+
+/// Ends a session started by `start_session`
+pub fn stop_session() -> Vec<u8> {
+    command(OpCode::StopSession, &[])
+}
+
+/// Puts the scanner into low power mode until `wakeup`, or until it is
+/// triggered or its low power timeout is disabled
+pub fn sleep() -> Vec<u8> {
+    command(OpCode::Sleep, &[])
+}
+
+/// Wakes a sleeping scanner
+///
+/// This is not a frame but a single null byte, which a sleeping scanner
+/// takes as the signal to wake up and an awake one ignores. Wait
+/// `WAKEUP_DELAY` before sending the next frame, anything arriving earlier
+/// is lost.
+pub fn wakeup() -> Vec<u8> {
+    vec![0x00]
+}
+
+/// How long a scanner needs to wake up after `wakeup`
+pub const WAKEUP_DELAY: Duration = Duration::from_millis(10);
+
+/// Discards the frames the scanner queued for sending, to start over
+/// after losing track of the conversation
+pub fn flush_queue() -> Vec<u8> {
+    command(OpCode::FlushQueue, &[])
+}
+
+/// Discards the Macro PDF labels the scanner buffered so far
+pub fn flush_macro_pdf() -> Vec<u8> {
+    command(OpCode::FlushMacroPdf, &[])
+}
+
+/// Restores the factory defaults of all parameters
+pub fn param_defaults() -> Vec<u8> {
+    command(OpCode::ParamDefaults, &[])
+}
+
+/// Asks what the scanner supports, answered by `CapabilitiesReply`
+pub fn capabilities_request() -> Vec<u8> {
+    command(OpCode::CapabilitiesRequest, &[])
+}
+
+/// Asks for the scanner's software revision, answered by `ReplyRevision`
+pub fn request_revision() -> Vec<u8> {
+    command(OpCode::RequestRevision, &[])
+}
+
+/// Sub-opcode of an `SSI_MGMT_COMMAND`, its first data byte
+///
+/// The named ones are the attribute operations of Remote Scanner
+/// Management, which the command carries. Support depends on the scanner
+/// model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MgmtSubcommand {
+    /// Lists the numbers of all attributes
+    AttrGetAll,
+    /// Reads the values of the attributes given
+    AttrGet,
+    /// Reads the attribute following the one given
+    AttrGetNext,
+    /// Reads part of a long attribute value, starting at an offset
+    AttrGetOffset,
+    /// Sets attribute values until the scanner is powered off
+    AttrSet,
+    /// Sets attribute values, surviving a power cycle
+    AttrStore,
+    Other(u8),
+}
+
+impl From<u8> for MgmtSubcommand {
+    fn from(val: u8) -> Self {
+        match val {
+            0x02 => MgmtSubcommand::AttrGetAll,
+            0x03 => MgmtSubcommand::AttrGet,
+            0x04 => MgmtSubcommand::AttrGetNext,
+            0x05 => MgmtSubcommand::AttrGetOffset,
+            0x06 => MgmtSubcommand::AttrSet,
+            0x07 => MgmtSubcommand::AttrStore,
+            _ => MgmtSubcommand::Other(val),
+        }
+    }
+}
+
+impl From<MgmtSubcommand> for u8 {
+    fn from(val: MgmtSubcommand) -> Self {
+        match val {
+            MgmtSubcommand::AttrGetAll => 0x02,
+            MgmtSubcommand::AttrGet => 0x03,
+            MgmtSubcommand::AttrGetNext => 0x04,
+            MgmtSubcommand::AttrGetOffset => 0x05,
+            MgmtSubcommand::AttrSet => 0x06,
+            MgmtSubcommand::AttrStore => 0x07,
+            MgmtSubcommand::Other(val) => val,
+        }
+    }
+}
+
+/// A management command, given by `subcommand` and its `payload`
+///
+/// Making parameters survive a power cycle doesn't need one, see
+/// `Scanner::set_params`. Fails if `payload` doesn't fit a frame.
+pub fn ssi_mgmt(
+    subcommand: MgmtSubcommand,
+    payload: &[u8],
+) -> Result<Vec<u8>, SsiError> {
+    let mut data = vec![subcommand.into()];
+    data.extend_from_slice(payload);
+
+    Message {
+        opcode: OpCode::SsiMgmtCommand,
+        source: Source::Host,
+        status: Status::default(),
+        data,
+    }
+    .encode()
+}
+
+/// Sets parameters until the scanner is powered off
+///
+/// Fails if `params` don't fit a frame, send them in several then.
+pub fn param_send(params: &[(ParamNumber, u8)]) -> Result<Vec<u8>, SsiError> {
+    wrap(param_send_payload(params, false))
+}
+
+/// Enables or disables every symbology at once, surviving a power cycle
+/// if `permanent`
+pub fn change_all_code_types(enable: bool, permanent: bool) -> Vec<u8> {
+    small_frame(change_all_code_types_payload(enable, permanent).to_vec())
+}
+
+/// Enables the symbologies `types`, surviving a power cycle if
+/// `permanent`
+///
+/// Preceded by `change_all_code_types(false, ..)`, this leaves only
+/// `types` enabled, see `Scanner::enable_only`. Fails on symbologies
+/// without a known parameter, see `ParamNumber::enabling`.
+pub fn enable_code_types(
+    types: &[ContentType],
+    permanent: bool,
+) -> Result<Vec<u8>, SsiError> {
+    let params = enable_params(types)?;
+
+    wrap(param_send_payload(&params, permanent))
+}
+
+/// Asks for parameter values, answered by a `ParamSend`, see
+/// `parse_param_send`
+///
+/// Fails if `params` don't fit a frame.
+pub fn param_request(params: &[ParamNumber]) -> Result<Vec<u8>, SsiError> {
+    wrap(param_request_payload(params))
+}
+
+/// Shows the aiming pattern without scanning, until `aim_off`
+pub fn aim_on() -> Vec<u8> {
+    command(OpCode::AimOn, &[])
+}
+
+pub fn aim_off() -> Vec<u8> {
+    command(OpCode::AimOff, &[])
+}
+
+/// Keeps the illumination LEDs on, independently of the aiming pattern,
+/// until `illumination_off`
+pub fn illumination_on() -> Vec<u8> {
+    command(OpCode::IlluminationOn, &[])
+}
+
+pub fn illumination_off() -> Vec<u8> {
+    command(OpCode::IlluminationOff, &[])
+}
+
+/// Turns on the LEDs selected by the bits of `led`
+pub fn led_on(led: u8) -> Vec<u8> {
+    command(OpCode::LedOn, &[led])
+}
+
+/// Turns off the LEDs selected by the bits of `led`
+pub fn led_off(led: u8) -> Vec<u8> {
+    command(OpCode::LedOff, &[led])
+}
+
+/// The scanner's beep patterns, short or long beeps in high or low tone
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeepCode {
+    OneShortHigh = 0x00,
+    TwoShortHigh = 0x01,
+    ThreeShortHigh = 0x02,
+    FourShortHigh = 0x03,
+    FiveShortHigh = 0x04,
+    OneShortLow = 0x05,
+    TwoShortLow = 0x06,
+    ThreeShortLow = 0x07,
+    FourShortLow = 0x08,
+    FiveShortLow = 0x09,
+    OneLongHigh = 0x0a,
+    TwoLongHigh = 0x0b,
+    ThreeLongHigh = 0x0c,
+    FourLongHigh = 0x0d,
+    FiveLongHigh = 0x0e,
+    OneLongLow = 0x0f,
+    TwoLongLow = 0x10,
+    ThreeLongLow = 0x11,
+    FourLongLow = 0x12,
+    FiveLongLow = 0x13,
+    FastWarble = 0x14,
+    SlowWarble = 0x15,
+    HighLow = 0x16,
+    LowHigh = 0x17,
+    HighLowHigh = 0x18,
+    LowHighLow = 0x19,
+    HighHighLowLow = 0x1a,
+}
+
+/// Makes the scanner beep
+pub fn beep(code: BeepCode) -> Vec<u8> {
+    command(OpCode::Beep, &[code as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+
+    /// Checks that `frame` is a valid host frame without data, carrying
+    /// the opcode byte `opcode`
+    fn assert_bare(frame: &[u8], opcode: u8) {
+        assert_eq!(frame[..4], [0x04, opcode, 0x04, 0x00]);
+        let message = decode(frame).unwrap();
+        assert_eq!(u8::from(message.opcode), opcode);
+        assert_eq!(message.source, Source::Host);
+        assert_eq!(message.status, Status::default());
+        assert!(message.data.is_empty());
+    }
+
+    #[test]
+    fn scan_enable_disable() {
+        assert_bare(&scan_enable(), 0xe9);
+        assert_bare(&scan_disable(), 0xea);
+        assert_eq!(scan_enable(), [0x04, 0xe9, 0x04, 0x00, 0xff, 0x0f]);
+    }
+
+    #[test]
+    fn ssi_mgmt_leads_with_subcommand() {
+        let frame = ssi_mgmt(MgmtSubcommand::AttrGet, &[0x00, 0x01]).unwrap();
+        let message = decode(&frame).unwrap();
+        assert_eq!(message.opcode, OpCode::SsiMgmtCommand);
+        assert_eq!(message.source, Source::Host);
+        assert_eq!(message.data, [0x03, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn mgmt_subcommands_round_trip() {
+        for byte in 0..=u8::MAX {
+            assert_eq!(u8::from(MgmtSubcommand::from(byte)), byte);
+        }
+        assert_eq!(MgmtSubcommand::from(0x07), MgmtSubcommand::AttrStore);
+        assert_eq!(MgmtSubcommand::from(0x42), MgmtSubcommand::Other(0x42));
+    }
+
+    #[test]
+    fn capabilities_request_opcode() {
+        assert_bare(&capabilities_request(), 0xd4);
+    }
+
+    #[test]
+    fn aim_on_off() {
+        assert_bare(&aim_on(), 0xc5);
+        assert_bare(&aim_off(), 0xc4);
+    }
+
+    #[test]
+    fn message_round_trips() {
+        let messages = [
+            Message {
+                opcode: OpCode::Beep,
+                source: Source::Host,
+                status: Status::default(),
+                data: vec![BeepCode::OneShortHigh as u8],
+            },
+            Message {
+                opcode: OpCode::ParamSend,
+                source: Source::Host,
+                status: Status::ChangeType,
+                data: vec![0xff, 0x8c, 0x02],
+            },
+            Message {
+                opcode: OpCode::Other(0x42),
+                source: Source::Scanner,
+                status: Status::Continuation | Status::Retransmit,
+                data: vec![0xa5; 251],
+            },
+        ];
+
+        for message in messages {
+            let frame = message.clone().encode().unwrap();
+            let decoded = decode(&frame).unwrap();
+            assert_eq!(decoded.opcode, message.opcode);
+            assert_eq!(decoded.source, message.source);
+            assert_eq!(decoded.status, message.status);
+            assert_eq!(decoded.data, message.data);
+        }
+    }
+
+    #[test]
+    fn start_stop_session() {
+        assert_bare(&start_session(), 0xe4);
+        assert_bare(&stop_session(), 0xe5);
+    }
+
+    #[test]
+    fn flush_queue_macro_pdf() {
+        assert_bare(&flush_queue(), 0xd2);
+        assert_bare(&flush_macro_pdf(), 0x10);
+    }
+
+    #[test]
+    fn illumination_on_off() {
+        assert_bare(&illumination_on(), 0xc1);
+        assert_bare(&illumination_off(), 0xc0);
+    }
+
+    #[test]
+    fn too_many_params() {
+        let params = vec![(ParamNumber(0x01), 0x01); 126];
+        assert!(param_send(&params).is_err());
+        assert!(decode(&param_send(&params[..125]).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn param_defaults_opcode() {
+        assert_bare(&param_defaults(), 0xc8);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use crate::{DecodeError, OpCode, RawMessage};
+
+type Handler<'h> = Box<dyn FnMut(&RawMessage) + 'h>;
+type ErrorHandler<'h> = Box<dyn FnMut(&DecodeError) + 'h>;
+
+/// Routes received messages to handlers registered per opcode
+///
+/// Messages without a handler for their opcode go to the fallback set with
+/// `otherwise`, or are dropped if there is none.
+#[derive(Default)]
+pub struct Dispatcher<'h> {
+    handlers: HashMap<OpCode, Handler<'h>>,
+    fallback: Option<Handler<'h>>,
+    on_error: Option<ErrorHandler<'h>>,
+}
+
+impl<'h> Dispatcher<'h> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles messages with `opcode`, replacing an earlier handler for it
+    pub fn on(
+        &mut self,
+        opcode: OpCode,
+        handler: impl FnMut(&RawMessage) + 'h,
+    ) -> &mut Self {
+        self.handlers.insert(opcode, Box::new(handler));
+        self
+    }
+
+    /// Handles messages no other handler is registered for
+    pub fn otherwise(
+        &mut self,
+        handler: impl FnMut(&RawMessage) + 'h,
+    ) -> &mut Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Handles frames that couldn't be decoded
+    pub fn on_error(
+        &mut self,
+        handler: impl FnMut(&DecodeError) + 'h,
+    ) -> &mut Self {
+        self.on_error = Some(Box::new(handler));
+        self
+    }
+
+    pub fn dispatch(&mut self, message: &RawMessage) {
+        let handler = match self.handlers.get_mut(&message.opcode) {
+            Some(handler) => Some(handler),
+            None => self.fallback.as_mut(),
+        };
+
+        if let Some(handler) = handler {
+            handler(message);
+        }
+    }
+
+    pub fn error(&mut self, error: &DecodeError) {
+        if let Some(handler) = &mut self.on_error {
+            handler(error);
+        }
+    }
+}
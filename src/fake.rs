@@ -0,0 +1,366 @@
+//! A scanner simulated in memory, for testing against a realistic peer
+//!
+//! `FakeScanner` implements `Read` and `Write` and goes in place of the
+//! serial port: `Scanner::new(FakeScanner::new(), config)`. It answers host
+//! frames the way a scanner does, and produces scans on demand.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+
+use crate::command::{ack_payload, nack_payload, NackReason};
+use crate::param::{
+    encode_param, parse_param, parse_param_send, ParamNumber, NO_BEEP,
+};
+use crate::{
+    decode, wrap_into, DecodeError, Framer, OpCode, OwnedMessage, Source,
+    Status,
+};
+
+/// Host commands the fake answers, as listed in its `CapabilitiesReply`
+const SUPPORTED: [OpCode; 6] = [
+    OpCode::Ack,
+    OpCode::Nack,
+    OpCode::RequestRevision,
+    OpCode::ParamSend,
+    OpCode::ParamRequest,
+    OpCode::CapabilitiesRequest,
+];
+
+#[derive(Debug, Default)]
+pub struct FakeScanner {
+    /// Bytes waiting for the host to read them
+    outgoing: VecDeque<u8>,
+    /// Host bytes that don't make up a complete frame yet
+    incoming: Framer,
+    /// The last frame sent, for resending it on a NACK
+    last_frame: Vec<u8>,
+    /// Host frames to leave unanswered
+    drop_acks: usize,
+    revision: Vec<u8>,
+    params: HashMap<ParamNumber, u8>,
+    /// Every host frame received, ACKs included
+    pub received: Vec<OwnedMessage>,
+}
+
+impl FakeScanner {
+    pub fn new() -> Self {
+        FakeScanner {
+            revision: b"FAKE0001".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Sends a `DecodeData` frame, as if a label was scanned
+    pub fn scan(&mut self, content_type: u8, content: &[u8]) {
+        let mut data = vec![content_type];
+        data.extend_from_slice(content);
+        self.send(OpCode::DecodeData, Status::default(), &data);
+    }
+
+    /// Leaves the next `count` host frames unacknowledged, as if they got
+    /// lost on the line
+    pub fn drop_acks(&mut self, count: usize) {
+        self.drop_acks = count;
+    }
+
+    /// Sets the revision string sent in reply to `RequestRevision`
+    pub fn set_revision(&mut self, revision: &[u8]) {
+        self.revision = revision.to_vec();
+    }
+
+    /// The value of a parameter, as set by the host
+    pub fn param(&self, param: ParamNumber) -> Option<u8> {
+        self.params.get(&param).copied()
+    }
+
+    fn send(&mut self, opcode: OpCode, status: Status, data: &[u8]) {
+        let mut payload =
+            vec![opcode.into(), Source::Scanner.into(), status.into()];
+        payload.extend_from_slice(data);
+
+        wrap_into(&mut self.last_frame, &payload)
+            .expect("replies of the fake fit a frame");
+        self.outgoing.extend(&self.last_frame);
+    }
+
+    /// Sends an ACK, which is never resent
+    fn ack(&mut self) {
+        let mut frame = Vec::new();
+        wrap_into(&mut frame, &ack_payload(Source::Scanner, Status::default()))
+            .expect("an ACK fits a frame");
+        self.outgoing.extend(frame);
+    }
+
+    /// Sends a NACK, which is never resent
+    fn nack(&mut self, reason: NackReason) {
+        let mut frame = Vec::new();
+        wrap_into(&mut frame, &nack_payload(Source::Scanner, reason))
+            .expect("a NACK fits a frame");
+        self.outgoing.extend(frame);
+    }
+
+    fn handle(&mut self, message: OwnedMessage) {
+        let opcode = message.opcode;
+        let data = message.data.clone();
+        self.received.push(message);
+
+        match opcode {
+            OpCode::Ack => return,
+            OpCode::Nack => {
+                self.outgoing.extend(&self.last_frame);
+                return;
+            }
+            _ => (),
+        }
+        if self.drop_acks > 0 {
+            self.drop_acks -= 1;
+            return;
+        }
+
+        match opcode {
+            OpCode::RequestRevision => {
+                let revision = self.revision.clone();
+                self.send(OpCode::ReplyRevision, Status::default(), &revision);
+            }
+            OpCode::ParamSend => {
+                self.params
+                    .extend(parse_param_send(&data).unwrap_or_default());
+                self.ack();
+            }
+            OpCode::CapabilitiesRequest => {
+                // 9600 baud only, no serial options, multipacket mode 1
+                let mut reply = vec![0x00, 0x01, 0x00, 0x01];
+                reply.extend(SUPPORTED.map(u8::from));
+                self.send(OpCode::CapabilitiesReply, Status::default(), &reply);
+            }
+            OpCode::ParamRequest => {
+                let mut reply = vec![NO_BEEP];
+                let mut numbers = &data[..];
+                while let Some((param, rest)) = parse_param(numbers) {
+                    if let Some(value) = self.params.get(&param) {
+                        encode_param(&mut reply, param);
+                        reply.push(*value);
+                    }
+                    numbers = rest;
+                }
+                self.send(OpCode::ParamSend, Status::default(), &reply);
+            }
+            _ => self.ack(),
+        }
+    }
+}
+
+impl Read for FakeScanner {
+    /// Fails with `TimedOut` when there is nothing to read, like a serial
+    /// port with a read timeout
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.outgoing.is_empty() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        let len = buf.len().min(self.outgoing.len());
+        for (byte, out) in self.outgoing.drain(..len).zip(buf.iter_mut()) {
+            *out = byte;
+        }
+
+        Ok(len)
+    }
+}
+
+impl Write for FakeScanner {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.incoming.push(buf);
+
+        while let Some(frame) = self.incoming.next_frame() {
+            let message = frame.and_then(|frame| {
+                decode(&frame).map(|message| message.to_owned())
+            });
+            match message {
+                Ok(message) => self.handle(message),
+                Err(DecodeError::InvalidChecksum { .. }) => {
+                    self.nack(NackReason::Resend);
+                }
+                Err(_) => (),
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::protocol::MAX_RETRIES;
+    use crate::scanner::bare_payload;
+    use crate::{
+        parse_capabilities, ContentType, Scanner, SsiConfig, SsiError,
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn connect() -> Scanner<FakeScanner> {
+        Scanner::new(FakeScanner::new(), SsiConfig::default())
+    }
+
+    fn last_received(scanner: &Scanner<FakeScanner>) -> OpCode {
+        scanner.get_ref().received.last().unwrap().opcode
+    }
+
+    #[test]
+    fn command_is_acked() {
+        let mut scanner = connect();
+        scanner.start_session().unwrap();
+
+        // The scanner's ACK isn't acknowledged itself
+        assert_eq!(last_received(&scanner), OpCode::StartSession);
+        assert_eq!(scanner.into_inner().received.len(), 1);
+    }
+
+    #[test]
+    fn scan_is_acked() {
+        let mut scanner = connect();
+        scanner.get_mut().scan(ContentType::Code128 as u8, b"hello");
+
+        let scan = scanner.next_decode(TIMEOUT).unwrap().unwrap();
+        assert_eq!(scan.opcode, OpCode::DecodeData);
+        assert_eq!(scan.source, Source::Scanner);
+        assert_eq!(scan.data, b"\x03hello");
+        assert_eq!(last_received(&scanner), OpCode::Ack);
+
+        assert!(scanner.next_decode(TIMEOUT).unwrap().is_none());
+    }
+
+    #[test]
+    fn scan_while_awaiting_ack_is_kept() {
+        let mut scanner = connect();
+        scanner
+            .get_mut()
+            .scan(ContentType::Ean13 as u8, b"4006381333931");
+        scanner.stop_session().unwrap();
+
+        let scan = scanner.next_decode(TIMEOUT).unwrap().unwrap();
+        assert_eq!(&scan.data[1..], b"4006381333931");
+    }
+
+    /// Whether each frame the fake received is marked as retransmission
+    fn retransmits(scanner: &Scanner<FakeScanner>) -> Vec<bool> {
+        let received = &scanner.get_ref().received;
+        received
+            .iter()
+            .map(|message| message.status.contains(Status::Retransmit))
+            .collect()
+    }
+
+    #[test]
+    fn lost_ack_is_retransmitted() {
+        let mut scanner = connect();
+        scanner.get_mut().drop_acks(1);
+
+        let payload = bare_payload(OpCode::StartSession);
+        scanner.send_and_await_ack(&payload, TIMEOUT).unwrap();
+        assert_eq!(retransmits(&scanner), [false, true]);
+        assert_eq!(last_received(&scanner), OpCode::StartSession);
+    }
+
+    #[test]
+    fn lost_acks_give_up() {
+        let mut scanner = connect();
+        scanner.get_mut().drop_acks(usize::from(MAX_RETRIES) + 1);
+
+        let payload = bare_payload(OpCode::StartSession);
+        let result = scanner.send_and_await_ack(&payload, TIMEOUT);
+        assert!(matches!(result, Err(SsiError::Timeout)));
+        assert_eq!(retransmits(&scanner), [false, true, true, true]);
+
+        // The next command goes through again, as a fresh frame
+        scanner.send_and_await_ack(&payload, TIMEOUT).unwrap();
+        assert_eq!(retransmits(&scanner)[4..], [false]);
+    }
+
+    #[test]
+    fn params_round_trip() {
+        let mut scanner = connect();
+        let params =
+            [(ParamNumber::BEEPER_VOLUME, 2), (ParamNumber(0x1234), 7)];
+        scanner.set_params(&params, false).unwrap();
+        assert_eq!(
+            scanner.get_ref().param(ParamNumber::BEEPER_VOLUME),
+            Some(2)
+        );
+
+        let numbers = [ParamNumber::BEEPER_VOLUME, ParamNumber(0x1234)];
+        assert_eq!(scanner.get_params(&numbers).unwrap(), params);
+        assert_eq!(last_received(&scanner), OpCode::Ack);
+
+        // Unknown parameters are left out
+        let numbers = [ParamNumber::BEEPER_TONE];
+        assert_eq!(scanner.get_params(&numbers).unwrap(), []);
+    }
+
+    #[test]
+    fn revision() {
+        let mut scanner = connect();
+        scanner.get_mut().set_revision(b"NBRPVAAM 21 ENG");
+
+        let revision = scanner.revision().unwrap();
+        assert_eq!(revision.software, "NBRPVAAM");
+        assert_eq!(revision.board.as_deref(), Some("21"));
+        assert_eq!(revision.other, ["ENG"]);
+        assert_eq!(last_received(&scanner), OpCode::Ack);
+    }
+
+    #[test]
+    fn capabilities() {
+        let mut scanner = connect();
+        let replies = scanner
+            .exchange(
+                &bare_payload(OpCode::CapabilitiesRequest),
+                TIMEOUT,
+                |message| message.opcode == OpCode::CapabilitiesReply,
+            )
+            .unwrap();
+
+        assert_eq!(u8::from(replies[0].opcode), 0xd3);
+        let capabilities = parse_capabilities(&replies[0].data).unwrap();
+        assert_eq!(capabilities.baud_rates, 0x0001);
+        assert_eq!(capabilities.multipacket, 1);
+        assert_eq!(capabilities.opcodes, SUPPORTED);
+    }
+
+    #[test]
+    fn corrupted_scan_is_nacked() {
+        let mut scanner = connect();
+        let fake = scanner.get_mut();
+        fake.scan(ContentType::Code39 as u8, b"CODE39");
+        fake.outgoing[6] ^= 0x01;
+
+        // Only the resent frame makes it through
+        let scan = scanner.next_decode(TIMEOUT).unwrap().unwrap();
+        assert_eq!(&scan.data[1..], b"CODE39");
+        let received = &scanner.get_ref().received;
+        assert_eq!(received[0].opcode, OpCode::Nack);
+        assert_eq!(received[0].data, [NackReason::Resend.into()]);
+        assert_eq!(received[1].opcode, OpCode::Ack);
+        assert_eq!(received.len(), 2);
+    }
+
+    #[test]
+    fn garbage_is_skipped() {
+        let mut scanner = connect();
+        scanner.get_mut().outgoing.extend([0x02, 0x00]);
+        scanner.start_session().unwrap();
+
+        // Taken for the start of a long frame until it times out
+        scanner.get_mut().outgoing.push_back(0xf0);
+        scanner.get_mut().scan(ContentType::Code128 as u8, b"late");
+        let scan = scanner.next_decode(Duration::from_secs(1)).unwrap();
+        assert_eq!(&scan.unwrap().data[1..], b"late");
+    }
+}
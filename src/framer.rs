@@ -0,0 +1,323 @@
+use std::io::{self, Read};
+
+use crate::checksum::calc_checksum;
+use crate::{decode_with, DecodeError, OwnedMessage, SsiConfig, SsiError};
+
+/// Cuts a stream of bytes into frames
+///
+/// Bytes are pushed as they are read, in chunks of any size, and come back
+/// out as frames, using the length byte each frame starts with. Bytes not
+/// making up a complete frame yet, down to a lone length byte or nothing at
+/// all, are kept for the next chunk.
+///
+/// A frame failing its checksum, or a length byte too small for any frame,
+/// suggests garbage on the line or a lost byte rather than a bad frame. The
+/// error is returned once, then the framer moves ahead a byte at a time
+/// until a valid frame starts. Frames with a valid checksum that fail to
+/// decode otherwise are returned as errors and dropped as a whole.
+///
+/// Garbage can also claim a length beyond what arrived so far, which looks
+/// no different from a frame still arriving. The framer waits for the rest
+/// of it, until the reader gives up on it with `expire`.
+#[derive(Debug, Clone, Default)]
+pub struct Framer {
+    buf: Vec<u8>,
+    config: SsiConfig,
+    /// Whether the framer is looking for the start of a valid frame
+    resyncing: bool,
+}
+
+impl Framer {
+    pub fn new(config: SsiConfig) -> Self {
+        Framer {
+            buf: Vec::new(),
+            config,
+            resyncing: false,
+        }
+    }
+
+    /// Adds bytes as they were read
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Takes the next frame off the buffer, `None` if there isn't a
+    /// complete one yet
+    ///
+    /// Call until it returns `None` to get all frames of a chunk.
+    pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, DecodeError>> {
+        loop {
+            let &length = self.buf.first()?;
+            let len = usize::from(length) + 2;
+            let result = if length < 4 {
+                Err(DecodeError::InvalidLengthByte(length))
+            } else if self.buf.len() < len {
+                // Past garbage, a complete frame ahead is a safer bet than
+                // the length byte. Otherwise, one in a frame's data could
+                // match by chance.
+                if !self.resyncing {
+                    return None;
+                }
+                let start = (1..self.buf.len()).find(|&i| self.is_frame(i))?;
+                self.buf.drain(..start);
+                continue;
+            } else {
+                decode_with(&self.buf[..len], &self.config).map(|_| ())
+            };
+
+            match result {
+                Ok(()) => {
+                    self.resyncing = false;
+                    return Some(Ok(self.buf.drain(..len).collect()));
+                }
+                Err(
+                    e @ (DecodeError::InvalidChecksum { .. }
+                    | DecodeError::InvalidLengthByte(_)),
+                ) => {
+                    self.buf.remove(0);
+                    if !std::mem::replace(&mut self.resyncing, true) {
+                        return Some(Err(e));
+                    }
+                }
+                Err(e) => {
+                    self.resyncing = false;
+                    self.buf.drain(..len);
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    /// Whether a complete frame with a matching checksum starts at `start`
+    fn is_frame(&self, start: usize) -> bool {
+        let rest = &self.buf[start..];
+        let Some(&length) = rest.first() else {
+            return false;
+        };
+        let len = usize::from(length) + 2;
+        if length < 4 || rest.len() < len {
+            return false;
+        }
+
+        let [length, payload @ .., checksum1, checksum2] = &rest[..len] else {
+            return false;
+        };
+        let sum = calc_checksum(*length, payload);
+        self.config.checksum.matches(sum, [*checksum1, *checksum2])
+    }
+
+    /// Gives up on the incomplete frame at the start of the buffer, as
+    /// when the rest of it doesn't arrive in time
+    ///
+    /// Skips ahead to the next complete frame with a matching checksum, or
+    /// empties the buffer if there is none, and returns the frame given up
+    /// as `DecodeError::Truncated`. Returns `None` if the buffer is empty
+    /// already.
+    pub fn expire(&mut self) -> Option<DecodeError> {
+        let &length = self.buf.first()?;
+        let error = if length < 4 {
+            DecodeError::InvalidLengthByte(length)
+        } else {
+            DecodeError::Truncated {
+                expected: usize::from(length) + 2,
+                available: self.buf.len(),
+            }
+        };
+
+        let start = (1..self.buf.len())
+            .find(|&i| self.is_frame(i))
+            .unwrap_or(self.buf.len());
+        self.buf.drain(..start);
+        self.resyncing = false;
+
+        Some(error)
+    }
+
+    /// The bytes kept for the next chunk
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Empties the buffer, returning what was in it
+    pub fn take_buffered(&mut self) -> Vec<u8> {
+        self.resyncing = false;
+        std::mem::take(&mut self.buf)
+    }
+}
+
+/// Decodes every frame `reader` delivers, like a capture or a hex dump
+/// turned back into bytes, with the default `SsiConfig`
+pub fn decode_stream(
+    reader: impl Read,
+) -> impl Iterator<Item = Result<OwnedMessage, SsiError>> {
+    decode_stream_with(reader, SsiConfig::default())
+}
+
+/// Decodes every frame `reader` delivers according to `config`
+///
+/// Framing is done by a `Framer`, as for a live port. An incomplete frame
+/// left at the end of `reader` is reported as
+/// `DecodeError::Truncated`, see `Framer::expire`, and decoding
+/// goes on with any complete frame behind it. The first read error ends
+/// the stream the same way, once it was passed on.
+pub fn decode_stream_with(
+    mut reader: impl Read,
+    config: SsiConfig,
+) -> impl Iterator<Item = Result<OwnedMessage, SsiError>> {
+    let mut framer = Framer::new(config.clone());
+    let mut done = false;
+    let mut chunk = [0; 256];
+
+    std::iter::from_fn(move || loop {
+        if let Some(frame) = framer.next_frame() {
+            let message = frame.and_then(|frame| {
+                decode_with(&frame, &config).map(|message| message.to_owned())
+            });
+            return Some(message.map_err(SsiError::from));
+        }
+        if done {
+            return framer.expire().map(|e| Err(e.into()));
+        }
+
+        match reader.read(&mut chunk) {
+            Ok(0) => done = true,
+            Ok(t) => framer.push(&chunk[..t]),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => {
+                done = true;
+                return Some(Err(e.into()));
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, wrap, OpCode};
+
+    fn decode_frame(data: &[u8]) -> Vec<u8> {
+        let mut payload = vec![OpCode::DecodeData.into(), 0x00, 0x00];
+        payload.extend_from_slice(data);
+        wrap(payload).unwrap()
+    }
+
+    #[test]
+    fn back_to_back_frames() {
+        let first = decode_frame(b"\x03first");
+        let second = decode_frame(b"\x03second");
+        let mut framer = Framer::default();
+        framer.push(&[&first[..], &second[..]].concat());
+
+        assert_eq!(framer.next_frame().unwrap().unwrap(), first);
+        assert_eq!(framer.next_frame().unwrap().unwrap(), second);
+        assert!(framer.next_frame().is_none());
+        assert!(framer.buffered().is_empty());
+
+        let message = decode(&second).unwrap();
+        assert_eq!(message.data, b"\x03second");
+    }
+
+    #[test]
+    fn frame_split_across_chunks() {
+        let first = decode_frame(b"\x03first");
+        let second = decode_frame(b"\x03second");
+        let stream = [&first[..], &second[..]].concat();
+        let (chunk1, chunk2) = stream.split_at(first.len() + 3);
+        let mut framer = Framer::default();
+
+        framer.push(chunk1);
+        assert_eq!(framer.next_frame().unwrap().unwrap(), first);
+        assert!(framer.next_frame().is_none());
+        assert_eq!(framer.buffered(), &second[..3]);
+
+        framer.push(chunk2);
+        assert_eq!(framer.next_frame().unwrap().unwrap(), second);
+        assert!(framer.next_frame().is_none());
+    }
+
+    #[test]
+    fn garbage_before_frame() {
+        let frame = decode_frame(b"\x03data");
+        let mut framer = Framer::default();
+        framer.push(&[&[0x02, 0x17, 0x42][..], &frame].concat());
+
+        // Reported once, however many bytes are skipped
+        assert!(matches!(
+            framer.next_frame(),
+            Some(Err(DecodeError::InvalidLengthByte(0x02)))
+        ));
+        assert_eq!(framer.next_frame().unwrap().unwrap(), frame);
+        assert!(framer.next_frame().is_none());
+    }
+
+    #[test]
+    fn corrupted_frame() {
+        let mut corrupted = decode_frame(b"\x03lost");
+        corrupted[5] ^= 0x01;
+        let frame = decode_frame(b"\x03data");
+        let mut framer = Framer::default();
+        framer.push(&[&corrupted[..], &frame].concat());
+
+        assert!(matches!(
+            framer.next_frame(),
+            Some(Err(DecodeError::InvalidChecksum { .. }))
+        ));
+        assert_eq!(framer.next_frame().unwrap().unwrap(), frame);
+        assert!(framer.next_frame().is_none());
+    }
+
+    #[test]
+    fn garbage_claiming_a_long_frame() {
+        let frame = decode_frame(b"\x03data");
+        let mut framer = Framer::default();
+        framer.push(&[&[0xf0][..], &frame].concat());
+
+        // Could just as well be a long frame still arriving
+        assert!(framer.next_frame().is_none());
+        assert!(matches!(
+            framer.expire(),
+            Some(DecodeError::Truncated {
+                expected: 0xf2,
+                available: 12
+            })
+        ));
+        assert_eq!(framer.next_frame().unwrap().unwrap(), frame);
+        assert!(framer.expire().is_none());
+    }
+
+    #[test]
+    fn large_partial_frame() {
+        // A frame in the data must not be taken for the start of one
+        let inner = decode_frame(b"\x03inner");
+        let mut data = vec![0x03];
+        data.extend_from_slice(&inner);
+        data.resize(250, 0x55);
+        let frame = decode_frame(&data);
+        let mut framer = Framer::default();
+
+        for chunk in frame.chunks(16) {
+            assert!(framer.next_frame().is_none());
+            framer.push(chunk);
+        }
+        assert_eq!(framer.next_frame().unwrap().unwrap(), frame);
+        assert!(framer.buffered().is_empty());
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let frame = decode_frame(b"\x03data");
+        let stream = [&frame[..], &frame[..5]].concat();
+        let mut messages = decode_stream(&stream[..]);
+
+        assert_eq!(messages.next().unwrap().unwrap().data, b"\x03data");
+        assert!(matches!(
+            messages.next(),
+            Some(Err(SsiError::Decode(DecodeError::Truncated {
+                expected: 11,
+                available: 5
+            })))
+        ));
+        assert!(messages.next().is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::{self, Write};
+
+use crate::{
+    diagnose_checksum, hex_dump, CapturedImage, CompleteMessage, ContentType,
+    DecodeError, MessageAssembler, OpCode, RawMessage, ScanData, ScannerEvent,
+    SsiConfig, SsiError,
+};
+
+/// Receives what `run_with` reads from the scanner
+pub trait ScanHandler {
+    /// A decoded frame, after it was acknowledged
+    ///
+    /// ACKs and NACKs from the scanner come through here as well, so the
+    /// handshake can be followed. Packets of multipacket messages arrive
+    /// one by one, see `MessageAssembler`.
+    fn on_message(&mut self, message: &RawMessage);
+
+    /// A frame that couldn't be decoded
+    fn on_error(&mut self, error: &DecodeError);
+
+    /// The scanner didn't reply to the previous keepalive ping, see
+    /// `SsiConfig::keepalive`
+    fn on_unresponsive(&mut self) {}
+
+    /// Answering a frame failed, like the scanner NACKing a keepalive ping
+    /// too often
+    fn on_handshake_error(&mut self, _error: &SsiError) {}
+}
+
+/// The output of `run`: a description of every message on stdout, or just
+/// the scans in keyboard wedge mode
+pub struct PrintHandler {
+    config: SsiConfig,
+    assembler: MessageAssembler,
+}
+
+impl PrintHandler {
+    /// Prints according to the output settings of `config`
+    pub fn new(config: SsiConfig) -> Self {
+        PrintHandler {
+            config,
+            assembler: MessageAssembler::new(),
+        }
+    }
+
+    fn print_message(
+        &self,
+        message: &RawMessage,
+        complete: &CompleteMessage,
+    ) -> io::Result<()> {
+        let RawMessage {
+            length,
+            opcode,
+            source,
+            status,
+            checksum_failed,
+            was_retransmit,
+            ..
+        } = *message;
+        let config = &self.config;
+        let data = &complete.data[..];
+        let mut out = io::stdout().lock();
+
+        if let Some(terminator) = config.wedge {
+            if opcode == OpCode::DecodeData {
+                let Some(scan) = ScanData::parse(data, config.code_id) else {
+                    eprintln!("Too short for a code ID");
+                    return Ok(());
+                };
+                out.write_all(scan.code_id.unwrap_or_default())?;
+                out.write_all(scan.content)?;
+                out.write_all(terminator.as_bytes())?;
+                out.flush()?;
+            }
+            return Ok(());
+        }
+
+        writeln!(out, "Length: {length}")?;
+        writeln!(out, "Opcode: {opcode:?}")?;
+        writeln!(out, "Source: {source:?}")?;
+        writeln!(out, "Status: {status:?}")?;
+        if checksum_failed {
+            writeln!(out, "Checksum failed, decoded anyway")?;
+        }
+        if was_retransmit {
+            writeln!(out, "Retransmitted")?;
+        }
+        if complete.packets > 1 {
+            writeln!(out, "Packets: {}", complete.packets)?;
+        }
+
+        let unknown = match opcode {
+            OpCode::Other(_) => true,
+            OpCode::DecodeData => data
+                .first()
+                .is_some_and(|t| ContentType::try_from(*t).is_err()),
+            _ => false,
+        };
+        if config.hex_dump && unknown {
+            // The checksum isn't kept, but is of no interest either
+            let header = [length, opcode.into(), source.into(), status.into()];
+            let frame = [&header[..], data].concat();
+            write!(out, "{}", hex_dump(&frame))?;
+        }
+
+        if let OpCode::ParamDefaults = opcode {
+            writeln!(out, "Scanner parameters reset to defaults")?;
+        }
+
+        if let (OpCode::Event, [event, ..]) = (opcode, data) {
+            match ScannerEvent::from(*event) {
+                ScannerEvent::PowerUp => writeln!(out, "Scanner powered up")?,
+                event => writeln!(out, "Event: {event:?}")?,
+            }
+        }
+
+        if let Some(image) = CapturedImage::from_message(complete) {
+            write!(out, "Image: {:?}", image.format)?;
+            if let (Some(width), Some(height)) = (image.width, image.height) {
+                write!(out, " {width}x{height}")?;
+            }
+            writeln!(out, ", {} bytes", image.data.len())?;
+        }
+
+        if let OpCode::DecodeData = opcode {
+            let Some(scan) = ScanData::parse(data, config.code_id) else {
+                writeln!(out, "Too short for a code ID")?;
+                return Ok(());
+            };
+
+            match scan.symbology() {
+                Some(content_type) => {
+                    writeln!(out, "Type: '{content_type}'")?;
+                    if let Some(aim_id) = content_type.aim_id() {
+                        writeln!(out, "AIM ID: '{aim_id}'")?;
+                    }
+                }
+                None => {
+                    writeln!(
+                        out,
+                        "Unknown type: '{:#04x}'",
+                        scan.content_type
+                    )?;
+                }
+            }
+
+            if let Some(code_id) = scan.code_id {
+                let code_id = String::from_utf8_lossy(code_id);
+                writeln!(out, "Code ID: '{code_id}'")?;
+            }
+
+            match config.encoding.decode(scan.content) {
+                Some(decoded) => writeln!(out, "Decoded msg: '{decoded}'")?,
+                None => {
+                    out.write_all(b"Decoded msg: '")?;
+                    out.write_all(scan.content)?;
+                    out.write_all(b"'\n")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints opcode and data of a single packet, for `quiet_decode`
+    fn print_quiet(&self, message: &RawMessage) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        writeln!(out, "Opcode: {:?}", message.opcode)?;
+        out.write_all(message.data)?;
+        writeln!(out)
+    }
+
+    fn print_error(&self, error: &DecodeError) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        writeln!(out, "Error decoding data: {error}")?;
+        if let DecodeError::InvalidChecksum { frame, .. } = error {
+            if let Some(format) = diagnose_checksum(frame, self.config.checksum)
+            {
+                writeln!(out, "Checksum matches {format:?}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ScanHandler for PrintHandler {
+    fn on_message(&mut self, message: &RawMessage) {
+        // Whatever reads stdout going away is no reason to stop reading
+        // the scanner
+        let result = if self.config.quiet_decode && self.config.wedge.is_none()
+        {
+            // Packets as they come, without copying them together
+            self.print_quiet(message)
+        } else if let Some(complete) = self.assembler.push(message) {
+            self.print_message(message, &complete)
+        } else {
+            // Only complete messages are of interest
+            Ok(())
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error writing output: {e}");
+        }
+    }
+
+    fn on_error(&mut self, error: &DecodeError) {
+        // Keep stdout clean for whatever consumes the scans
+        if self.config.wedge.is_some() {
+            eprintln!("Error decoding data: {error}");
+            return;
+        }
+
+        if let Err(e) = self.print_error(error) {
+            eprintln!("Error writing output: {e}");
+        }
+    }
+
+    fn on_unresponsive(&mut self) {
+        eprintln!("Scanner not responding");
+    }
+
+    fn on_handshake_error(&mut self, error: &SsiError) {
+        eprintln!("Handshake failed: {error}");
+    }
+}
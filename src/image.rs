@@ -0,0 +1,138 @@
+//! Snapshots and video of imaging scanners
+//!
+//! In image capture mode the scanner sends the picture it took as an
+//! `ImageData` message, split into packets like any other long message.
+//! The data is a complete image file, so format and dimensions are read
+//! from the file's own header. In video mode, a continuous series of
+//! `VideoData` messages follows, each one a JPEG frame.
+
+use crate::{CompleteMessage, OpCode, RawMessage, Status};
+
+/// Start of image marker every JPEG file begins with
+const JPEG_SOI: [u8; 2] = [0xff, 0xd8];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Bmp,
+    Tiff,
+    Unknown,
+}
+
+/// A snapshot put back together from its packets, see `MessageAssembler`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedImage {
+    pub format: ImageFormat,
+    /// Dimensions in pixels, if the header gave them away
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// The image file
+    pub data: Vec<u8>,
+}
+
+impl CapturedImage {
+    /// The image carried by `message`, if it is an `ImageData` message
+    pub fn from_message(message: &CompleteMessage) -> Option<Self> {
+        if message.opcode != OpCode::ImageData {
+            return None;
+        }
+
+        Some(CapturedImage::parse(message.data.clone()))
+    }
+
+    /// Reads format and dimensions from the header of `data`
+    pub fn parse(data: Vec<u8>) -> Self {
+        let (format, dimensions) = match &data[..] {
+            _ if data.starts_with(&JPEG_SOI) => {
+                (ImageFormat::Jpeg, jpeg_dimensions(&data))
+            }
+            [b'B', b'M', ..] => (ImageFormat::Bmp, bmp_dimensions(&data)),
+            [b'I', b'I', 0x2a, 0x00, ..] | [b'M', b'M', 0x00, 0x2a, ..] => {
+                (ImageFormat::Tiff, None)
+            }
+            _ => (ImageFormat::Unknown, None),
+        };
+
+        CapturedImage {
+            format,
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+            data,
+        }
+    }
+}
+
+/// Width and height from the `BITMAPINFOHEADER`
+fn bmp_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let width = i32::from_le_bytes(data.get(18..22)?.try_into().ok()?);
+    // Negative for images stored top to bottom
+    let height = i32::from_le_bytes(data.get(22..26)?.try_into().ok()?);
+
+    Some((width.unsigned_abs(), height.unsigned_abs()))
+}
+
+/// Width and height from the first start of frame segment
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    // Behind the start of image marker
+    let mut rest = data.get(2..)?;
+
+    loop {
+        let [0xff, marker, len1, len2, segment @ ..] = rest else {
+            return None;
+        };
+        // SOF0 to SOF15, but for DHT, JPG and DAC sharing the range
+        if let 0xc0..=0xcf = marker {
+            if !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                let [_precision, h1, h2, w1, w2, ..] = segment else {
+                    return None;
+                };
+                let height = u16::from_be_bytes([*h1, *h2]);
+                let width = u16::from_be_bytes([*w1, *w2]);
+                return Some((u32::from(width), u32::from(height)));
+            }
+        }
+
+        // The length covers itself but not the marker
+        let len = usize::from(u16::from_be_bytes([*len1, *len2]));
+        rest = rest.get(2 + len..)?;
+    }
+}
+
+/// Puts video frames back together from their `VideoData` packets
+///
+/// Video packets come fast and the host can't ask for lost ones, so
+/// unlike `MessageAssembler` this doesn't rely on seeing every packet. A
+/// packet starting a new JPEG file always starts a new frame, dropping an
+/// unfinished one. Packets without a frame to add to are dropped until the
+/// next frame starts, as are frames that aren't JPEG files.
+#[derive(Debug, Default)]
+pub struct VideoAssembler {
+    pending: Option<Vec<u8>>,
+}
+
+impl VideoAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a packet, returning the frame once it is complete
+    ///
+    /// Frames other than `VideoData` are ignored.
+    pub fn push(&mut self, message: &RawMessage) -> Option<Vec<u8>> {
+        if message.opcode != OpCode::VideoData {
+            return None;
+        }
+
+        if message.data.starts_with(&JPEG_SOI) {
+            self.pending = Some(message.data.to_vec());
+        } else if let Some(pending) = &mut self.pending {
+            pending.extend_from_slice(message.data);
+        }
+
+        if message.status.contains(Status::Continuation) {
+            return None;
+        }
+
+        self.pending.take()
+    }
+}
@@ -1,17 +1,71 @@
-use std::io::{self, Write};
-use std::time::Duration;
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use bitflags::bitflags;
+use tokio::sync::{mpsc, watch};
+
+mod assembler;
+mod builder;
+mod capture;
+mod checksum;
+mod command;
+mod dispatch;
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+mod framer;
+mod handler;
+mod image;
+mod param;
+mod protocol;
+mod reply;
+mod scanner;
+pub mod symbology;
+mod transcript;
+
+pub use assembler::{CompleteMessage, MessageAssembler};
+pub use builder::ScannerBuilder;
+pub use capture::{read_capture, Capture};
+use checksum::calc_checksum;
+pub use checksum::{ByteOrder, ChecksumFormat, ChecksumPolicy};
+pub use command::{
+    ack, aim_off, aim_on, beep, capabilities_request, change_all_code_types,
+    enable_code_types, flush_macro_pdf, flush_queue, illumination_off,
+    illumination_on, led_off, led_on, nack, param_defaults, param_request,
+    param_send, request_revision, scan_disable, scan_enable, sleep, ssi_mgmt,
+    start_session, stop_session, wakeup, BeepCode, Message, MgmtSubcommand,
+    NackReason, WAKEUP_DELAY,
+};
+pub use dispatch::Dispatcher;
+pub use framer::{decode_stream, decode_stream_with, Framer};
+pub use handler::{PrintHandler, ScanHandler};
+pub use image::{CapturedImage, ImageFormat, VideoAssembler};
+pub use param::{parse_param_send, CodeId, ParamNumber, TransmitFormat};
+pub use reply::{parse_capabilities, parse_revision, Capabilities, Revision};
+use scanner::{bare_payload, revision_request, READ_TIMEOUT};
+pub use scanner::{LineEvent, Scanner};
+pub use transcript::{hex_dump, transcript, Direction};
 
 bitflags! {
-    #[derive(Debug)]
-    struct Status: u8 {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Status: u8 {
         const Retransmit = 1;
         const Continuation = 1 << 1;
         const ChangeType = 1 << 3;
     }
 }
 
+impl Status {
+    /// Bits set that none of the flags above stand for, as some firmware
+    /// uses reserved bits
+    pub fn unknown_bits(self) -> u8 {
+        self.bits() & !Status::all().bits()
+    }
+}
+
 impl Default for Status {
     fn default() -> Status {
         Status::empty()
@@ -24,25 +78,348 @@ impl From<Status> for u8 {
     }
 }
 
-struct RawMessage<'a> {
-    length: u8,
-    opcode: OpCode,
-    source: Source,
-    status: Status,
-    data: &'a [u8],
+/// A decoded frame, borrowing its data from the receive buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawMessage<'a> {
+    pub length: u8,
+    pub opcode: OpCode,
+    pub source: Source,
+    pub status: Status,
+    pub data: &'a [u8],
+    /// Set if the checksum didn't match, which only gets through decoding
+    /// with `ChecksumPolicy::BestEffort`
+    pub checksum_failed: bool,
+    /// Set if the sender marked the frame as a retransmission
+    /// (`Status::Retransmit`), so it may duplicate an earlier one
+    pub was_retransmit: bool,
+}
+
+impl<'a> RawMessage<'a> {
+    /// The event reported, if this is an `Event` frame
+    pub fn event(&self) -> Option<ScannerEvent> {
+        match (self.opcode, self.data) {
+            (OpCode::Event, [event, ..]) => Some(ScannerEvent::from(*event)),
+            _ => None,
+        }
+    }
+
+    /// The scan taken apart, if this is a `DecodeData` frame, see
+    /// `ScanData::parse`
+    pub fn scan_data(&self, code_id: CodeId) -> Option<ScanData<'a>> {
+        match self.opcode {
+            OpCode::DecodeData => ScanData::parse(self.data, code_id),
+            _ => None,
+        }
+    }
+
+    /// Copies the message out of the receive buffer
+    pub fn to_owned(&self) -> OwnedMessage {
+        OwnedMessage {
+            length: self.length,
+            opcode: self.opcode,
+            source: self.source,
+            status: self.status,
+            data: self.data.to_vec(),
+            checksum_failed: self.checksum_failed,
+            was_retransmit: self.was_retransmit,
+        }
+    }
+}
+
+/// One line summing up the frame, like
+/// `DECODE_DATA from Scanner, 14 data bytes, Status(Continuation)`
+impl fmt::Display for RawMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {:?}, {} data bytes",
+            self.opcode,
+            self.source,
+            self.data.len()
+        )?;
+        if !self.status.is_empty() {
+            write!(f, ", {:?}", self.status)?;
+        }
+        if self.checksum_failed {
+            f.write_str(", checksum failed")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A `RawMessage` that owns its data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedMessage {
+    pub length: u8,
+    pub opcode: OpCode,
+    pub source: Source,
+    pub status: Status,
+    pub data: Vec<u8>,
+    pub checksum_failed: bool,
+    pub was_retransmit: bool,
+}
+
+/// A `DecodeData` payload taken apart
+///
+/// The layout depends on the scanner's code ID transmission setting:
+///
+/// - `CodeId::None`: content type byte, content
+/// - `CodeId::Symbol`: content type byte, one code ID character, content
+/// - `CodeId::Aim`: content type byte, the three character AIM identifier
+///   (`]`, symbology character, modifier), content
+///
+/// The content type byte is always there, the code ID is part of the data
+/// the scanner transmits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanData<'a> {
+    /// See `ContentType`
+    pub content_type: u8,
+    pub code_id: Option<&'a [u8]>,
+    pub content: &'a [u8],
+}
+
+impl<'a> ScanData<'a> {
+    /// Splits `data`, returning `None` if it is too short for `code_id`
+    pub fn parse(data: &'a [u8], code_id: CodeId) -> Option<Self> {
+        let (&content_type, rest) = data.split_first()?;
+        let id_len = match code_id {
+            CodeId::None => 0,
+            CodeId::Symbol => 1,
+            CodeId::Aim => 3,
+        };
+        if rest.len() < id_len {
+            return None;
+        }
+        let (id, content) = rest.split_at(id_len);
+
+        Some(ScanData {
+            content_type,
+            code_id: (id_len > 0).then_some(id),
+            content,
+        })
+    }
+
+    /// The symbology, `None` for content types unknown to the crate
+    pub fn symbology(&self) -> Option<ContentType> {
+        ContentType::try_from(self.content_type).ok()
+    }
 }
 
 #[derive(Debug)]
-enum DecodeError {
-    InvalidChecksum,
-    InvalidMessageLength,
+pub enum DecodeError {
+    /// The checksum carried by `frame` doesn't match its content
+    InvalidChecksum {
+        expected: u16,
+        computed: u16,
+        frame: Vec<u8>,
+    },
+    /// `len` bytes, which don't match the length byte or are too few for
+    /// what should be there
+    InvalidMessageLength { len: usize },
+    /// A frame starting with a length byte too small for any frame, taken
+    /// for garbage on the line
+    InvalidLengthByte(u8),
+    /// Only `available` bytes of a frame `expected` to be that long arrived
+    /// before the reader gave up on it, see `Framer::expire`
+    Truncated { expected: usize, available: usize },
+    /// A `DecodeData` message without even the content type byte
+    EmptyDecodeData,
+    /// Opcode, source and status contradict each other, see
+    /// `SsiConfig::validate_frames`
+    InconsistentFrame { opcode: OpCode, source: Source },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidChecksum {
+                expected,
+                computed,
+                frame,
+            } => {
+                write!(
+                    f,
+                    "invalid checksum {expected:#06x}, computed \
+                     {computed:#06x} in frame"
+                )?;
+                for byte in frame {
+                    write!(f, " {byte:02x}")?;
+                }
+                Ok(())
+            }
+            DecodeError::InvalidMessageLength { len } => {
+                write!(f, "invalid message length {len}")
+            }
+            DecodeError::InvalidLengthByte(length) => {
+                write!(f, "invalid length byte {length:#04x}")
+            }
+            DecodeError::Truncated {
+                expected,
+                available,
+            } => {
+                write!(
+                    f,
+                    "frame cut short after {available} of {expected} bytes"
+                )
+            }
+            DecodeError::EmptyDecodeData => {
+                write!(f, "DecodeData without content type")
+            }
+            DecodeError::InconsistentFrame { opcode, source } => {
+                write!(f, "inconsistent frame: {opcode} from {source:?}")
+            }
+        }
+    }
 }
 
+impl std::error::Error for DecodeError {}
+
+/// Errors of the crate's I/O facing API
 #[derive(Debug)]
-enum OpCode {
+pub enum SsiError {
+    /// Opening or configuring the serial port failed
+    Serial(serialport::Error),
+    Io(io::Error),
+    Decode(DecodeError),
+    /// The scanner kept NACKing a host frame
+    RetransmitExhausted,
+    /// No (complete) reply within the given time
+    Timeout,
+    /// A parameter read back differently than it was written, `read` is
+    /// `None` if the scanner didn't report it at all
+    ParamMismatch {
+        param: ParamNumber,
+        written: u8,
+        read: Option<u8>,
+    },
+    /// The crate doesn't know the parameter enabling this symbology, see
+    /// `ParamNumber::enabling`
+    UnsupportedSymbology(ContentType),
+    /// A payload of `len` bytes, more than a frame carries, see
+    /// `MAX_PAYLOAD`
+    PayloadTooLong {
+        len: usize,
+    },
+}
+
+impl fmt::Display for SsiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsiError::Serial(e) => write!(f, "serial port error: {e}"),
+            SsiError::Io(e) => write!(f, "I/O error: {e}"),
+            SsiError::Decode(e) => write!(f, "decode error: {e}"),
+            SsiError::RetransmitExhausted => {
+                write!(f, "frame NACKed too often, giving up")
+            }
+            SsiError::Timeout => write!(f, "timed out waiting for a reply"),
+            SsiError::ParamMismatch {
+                param,
+                written,
+                read: Some(read),
+            } => write!(
+                f,
+                "parameter {param:#x} reads back as {read:#04x} instead of {written:#04x}"
+            ),
+            SsiError::ParamMismatch {
+                param,
+                read: None,
+                ..
+            } => write!(f, "parameter {param:#x} missing from readback"),
+            SsiError::UnsupportedSymbology(content_type) => {
+                write!(f, "no parameter known for enabling {content_type}")
+            }
+            SsiError::PayloadTooLong { len } => write!(
+                f,
+                "payload of {len} bytes, a frame carries at most {MAX_PAYLOAD}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SsiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SsiError::Serial(e) => Some(e),
+            SsiError::Io(e) => Some(e),
+            SsiError::Decode(e) => Some(e),
+            SsiError::RetransmitExhausted
+            | SsiError::Timeout
+            | SsiError::ParamMismatch { .. }
+            | SsiError::UnsupportedSymbology(_)
+            | SsiError::PayloadTooLong { .. } => None,
+        }
+    }
+}
+
+impl From<serialport::Error> for SsiError {
+    fn from(val: serialport::Error) -> Self {
+        SsiError::Serial(val)
+    }
+}
+
+impl From<io::Error> for SsiError {
+    fn from(val: io::Error) -> Self {
+        SsiError::Io(val)
+    }
+}
+
+impl From<DecodeError> for SsiError {
+    fn from(val: DecodeError) -> Self {
+        SsiError::Decode(val)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpCode {
     Ack,
     Nack,
     DecodeData,
+    /// Sent by the host to restore the defaults, and by the scanner to
+    /// announce it did so. Cached parameter state is stale afterwards.
+    ParamDefaults,
+    /// Enables or disables every symbology at once
+    ChangeAllCodeTypes,
+    /// Parameter values, written by the host or reported by the scanner
+    ParamSend,
+    /// Asks the scanner for the values of the listed parameters
+    ParamRequest,
+    RequestRevision,
+    /// The scanner's software revision, answering `RequestRevision`
+    ReplyRevision,
+    /// Something happened on the scanner, see `ScannerEvent`
+    Event,
+    ScanEnable,
+    ScanDisable,
+    Beep,
+    LedOn,
+    LedOff,
+    /// Turns the aiming pattern on, on imaging scanners
+    AimOn,
+    AimOff,
+    /// Turns the illumination LEDs on, on imaging scanners
+    IlluminationOn,
+    IlluminationOff,
+    CapabilitiesRequest,
+    /// What the scanner supports, see `parse_capabilities`
+    CapabilitiesReply,
+    /// A snapshot from an imaging scanner, usually spread over many packets,
+    /// see `CapturedImage`
+    ImageData,
+    /// A video frame from an imaging scanner in video mode, see
+    /// `VideoAssembler`
+    VideoData,
+    /// Starts a decode session, as if the trigger was pulled
+    StartSession,
+    StopSession,
+    /// Puts the scanner into low power mode, see `wakeup`
+    Sleep,
+    /// Makes the scanner discard the frames it queued for sending
+    FlushQueue,
+    /// Makes the scanner discard the Macro PDF labels buffered so far
+    FlushMacroPdf,
+    /// Wraps a management command, the sub-opcode being the first data byte
+    SsiMgmtCommand,
     Other(u8),
 }
 
@@ -52,6 +429,32 @@ impl From<&u8> for OpCode {
             0xd0 => OpCode::Ack,
             0xd1 => OpCode::Nack,
             0xf3 => OpCode::DecodeData,
+            0xc8 => OpCode::ParamDefaults,
+            0xc9 => OpCode::ChangeAllCodeTypes,
+            0xc6 => OpCode::ParamSend,
+            0xc7 => OpCode::ParamRequest,
+            0xa3 => OpCode::RequestRevision,
+            0xa4 => OpCode::ReplyRevision,
+            0xf6 => OpCode::Event,
+            0xe9 => OpCode::ScanEnable,
+            0xea => OpCode::ScanDisable,
+            0xe6 => OpCode::Beep,
+            0xe7 => OpCode::LedOn,
+            0xe8 => OpCode::LedOff,
+            0xc5 => OpCode::AimOn,
+            0xc4 => OpCode::AimOff,
+            0xc1 => OpCode::IlluminationOn,
+            0xc0 => OpCode::IlluminationOff,
+            0xd4 => OpCode::CapabilitiesRequest,
+            0xd3 => OpCode::CapabilitiesReply,
+            0xb1 => OpCode::ImageData,
+            0xb4 => OpCode::VideoData,
+            0xe4 => OpCode::StartSession,
+            0xe5 => OpCode::StopSession,
+            0xeb => OpCode::Sleep,
+            0xd2 => OpCode::FlushQueue,
+            0x10 => OpCode::FlushMacroPdf,
+            0x80 => OpCode::SsiMgmtCommand,
             _ => OpCode::Other(*val),
         }
     }
@@ -63,15 +466,149 @@ impl From<OpCode> for u8 {
             OpCode::Ack => 0xd0,
             OpCode::Nack => 0xd1,
             OpCode::DecodeData => 0xf3,
+            OpCode::ParamDefaults => 0xc8,
+            OpCode::ChangeAllCodeTypes => 0xc9,
+            OpCode::ParamSend => 0xc6,
+            OpCode::ParamRequest => 0xc7,
+            OpCode::RequestRevision => 0xa3,
+            OpCode::ReplyRevision => 0xa4,
+            OpCode::Event => 0xf6,
+            OpCode::ScanEnable => 0xe9,
+            OpCode::ScanDisable => 0xea,
+            OpCode::Beep => 0xe6,
+            OpCode::LedOn => 0xe7,
+            OpCode::LedOff => 0xe8,
+            OpCode::AimOn => 0xc5,
+            OpCode::AimOff => 0xc4,
+            OpCode::IlluminationOn => 0xc1,
+            OpCode::IlluminationOff => 0xc0,
+            OpCode::CapabilitiesRequest => 0xd4,
+            OpCode::CapabilitiesReply => 0xd3,
+            OpCode::ImageData => 0xb1,
+            OpCode::VideoData => 0xb4,
+            OpCode::StartSession => 0xe4,
+            OpCode::StopSession => 0xe5,
+            OpCode::Sleep => 0xeb,
+            OpCode::FlushQueue => 0xd2,
+            OpCode::FlushMacroPdf => 0x10,
+            OpCode::SsiMgmtCommand => 0x80,
             OpCode::Other(val) => val,
         }
     }
 }
 
-#[derive(Debug)]
-enum Source {
+impl OpCode {
+    /// Every opcode with a dedicated variant
+    pub fn all() -> &'static [OpCode] {
+        &[
+            OpCode::Ack,
+            OpCode::Nack,
+            OpCode::DecodeData,
+            OpCode::ParamDefaults,
+            OpCode::ChangeAllCodeTypes,
+            OpCode::ParamSend,
+            OpCode::ParamRequest,
+            OpCode::RequestRevision,
+            OpCode::ReplyRevision,
+            OpCode::Event,
+            OpCode::ScanEnable,
+            OpCode::ScanDisable,
+            OpCode::Beep,
+            OpCode::LedOn,
+            OpCode::LedOff,
+            OpCode::AimOn,
+            OpCode::AimOff,
+            OpCode::IlluminationOn,
+            OpCode::IlluminationOff,
+            OpCode::CapabilitiesRequest,
+            OpCode::CapabilitiesReply,
+            OpCode::ImageData,
+            OpCode::VideoData,
+            OpCode::StartSession,
+            OpCode::StopSession,
+            OpCode::Sleep,
+            OpCode::FlushQueue,
+            OpCode::FlushMacroPdf,
+            OpCode::SsiMgmtCommand,
+        ]
+    }
+
+    /// The name used for the opcode in the SSI specification
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCode::Ack => "CMD_ACK",
+            OpCode::Nack => "CMD_NAK",
+            OpCode::DecodeData => "DECODE_DATA",
+            OpCode::ParamDefaults => "PARAM_DEFAULTS",
+            OpCode::ChangeAllCodeTypes => "CHANGE_ALL_CODE_TYPES",
+            OpCode::ParamSend => "PARAM_SEND",
+            OpCode::ParamRequest => "PARAM_REQUEST",
+            OpCode::RequestRevision => "REQUEST_REVISION",
+            OpCode::ReplyRevision => "REPLY_REVISION",
+            OpCode::Event => "EVENT",
+            OpCode::ScanEnable => "SCAN_ENABLE",
+            OpCode::ScanDisable => "SCAN_DISABLE",
+            OpCode::Beep => "BEEP",
+            OpCode::LedOn => "LED_ON",
+            OpCode::LedOff => "LED_OFF",
+            OpCode::AimOn => "AIM_ON",
+            OpCode::AimOff => "AIM_OFF",
+            OpCode::IlluminationOn => "ILLUMINATION_ON",
+            OpCode::IlluminationOff => "ILLUMINATION_OFF",
+            OpCode::CapabilitiesRequest => "CAPABILITIES_REQUEST",
+            OpCode::CapabilitiesReply => "CAPABILITIES_REPLY",
+            OpCode::ImageData => "IMAGE_DATA",
+            OpCode::VideoData => "VIDEO_DATA",
+            OpCode::StartSession => "START_SESSION",
+            OpCode::StopSession => "STOP_SESSION",
+            OpCode::Sleep => "SLEEP",
+            OpCode::FlushQueue => "FLUSH_QUEUE",
+            OpCode::FlushMacroPdf => "FLUSH_MACRO_PDF",
+            OpCode::SsiMgmtCommand => "SSI_MGMT_COMMAND",
+            OpCode::Other(_) => "Other",
+        }
+    }
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpCode::Other(val) => write!(f, "Other({val:#04x})"),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+/// What an `Event` frame reports, given by its first data byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScannerEvent {
+    /// A label was decoded
+    Decode,
+    /// The scanner just powered up. Some scanners announce this on connect,
+    /// it is not a scan.
+    PowerUp,
+    /// A parameter was set by scanning a programming label
+    ParamEntry,
+    Other(u8),
+}
+
+impl From<u8> for ScannerEvent {
+    fn from(val: u8) -> Self {
+        match val {
+            0x01 => ScannerEvent::Decode,
+            0x03 => ScannerEvent::PowerUp,
+            0x07 => ScannerEvent::ParamEntry,
+            _ => ScannerEvent::Other(val),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
     Scanner,
     Host,
+    /// Non-standard value, as used by some protocol converters
+    Other(u8),
 }
 
 impl From<&u8> for Source {
@@ -79,7 +616,7 @@ impl From<&u8> for Source {
         match val {
             0x00 => Source::Scanner,
             0x04 => Source::Host,
-            _ => unreachable!(),
+            _ => Source::Other(*val),
         }
     }
 }
@@ -89,13 +626,14 @@ impl From<Source> for u8 {
         match val {
             Source::Scanner => 0x00,
             Source::Host => 0x04,
+            Source::Other(val) => val,
         }
     }
 }
 
 #[repr(u8)]
-#[derive(Debug)]
-enum ContentType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
     Aztec = 0x2d,
     AztecRune = 0x2e,
     Bookland = 0x16,
@@ -135,12 +673,12 @@ enum ContentType {
     Isbt128Concat = 0x21,
     Issn = 0x36,
     Interleaved2of5 = 0x06,
-    Korean3of5 = 0x73, // Assuming here that '2 of 5' is a typo in the reference
+    Korean3of5 = 0x73,
     MacroMicroPdf = 0x9a,
     MacroPdf417 = 0x28,
     MacroQr = 0x29,
     Mailmark = 0xc3,
-    Matrix2of5 = 0x39,
+    Matrix2of5 = 0x71,
     Maxicode = 0x25,
     MicroPdf = 0x1a,
     MicroPdfCca = 0x1d,
@@ -278,124 +816,976 @@ impl TryFrom<u8> for ContentType {
     }
 }
 
-fn calc_checksum(size: u8, payload: &[u8]) -> u16 {
-    size as u16 + payload.iter().cloned().map(u16::from).sum::<u16>()
+impl ContentType {
+    /// Conventional name of the symbology, for showing to people
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContentType::Aztec => "Aztec",
+            ContentType::AztecRune => "Aztec Rune",
+            ContentType::Bookland => "Bookland EAN",
+            ContentType::Chinese2of5 => "Chinese 2 of 5",
+            ContentType::Codabar => "Codabar",
+            ContentType::Code11 => "Code 11",
+            ContentType::Code128 => "Code 128",
+            ContentType::Code16K => "Code 16K",
+            ContentType::Code32 => "Code 32",
+            ContentType::Code39 => "Code 39",
+            ContentType::Code39Ascii => "Code 39 Full ASCII",
+            ContentType::Code49 => "Code 49",
+            ContentType::Code93 => "Code 93",
+            ContentType::Coupon => "Coupon Code",
+            ContentType::CueCat => "CueCat",
+            ContentType::Discrete2of5 => "Discrete 2 of 5",
+            ContentType::DataMatrix => "Data Matrix",
+            ContentType::Dotcode => "DotCode",
+            ContentType::Ean13 => "EAN-13",
+            ContentType::Ean13Plus2 => "EAN-13 +2",
+            ContentType::Ean13Plus5 => "EAN-13 +5",
+            ContentType::Ean8 => "EAN-8",
+            ContentType::Ean8Plus2 => "EAN-8 +2",
+            ContentType::Ean8Plus5 => "EAN-8 +5",
+            ContentType::FrenchLottery => "French Lottery",
+            ContentType::GridMatrix => "Grid Matrix",
+            ContentType::Gs1_128 => "GS1-128",
+            ContentType::Gs1DataBarExpanded => "GS1 DataBar Expanded",
+            ContentType::Gs1DataBarLimited => "GS1 DataBar Limited",
+            ContentType::Gs1DataBar14 => "GS1 DataBar-14",
+            ContentType::Gs1DataMatrix => "GS1 Data Matrix",
+            ContentType::Gs1Qr => "GS1 QR",
+            ContentType::HanXin => "Han Xin",
+            ContentType::Iata => "IATA 2 of 5",
+            ContentType::Isbt128 => "ISBT 128",
+            ContentType::Isbt128Concat => "ISBT 128 Concatenated",
+            ContentType::Issn => "ISSN EAN",
+            ContentType::Interleaved2of5 => "Interleaved 2 of 5",
+            ContentType::Korean3of5 => "Korean 3 of 5",
+            ContentType::MacroMicroPdf => "Macro MicroPDF",
+            ContentType::MacroPdf417 => "Macro PDF417",
+            ContentType::MacroQr => "Macro QR",
+            ContentType::Mailmark => "Mailmark",
+            ContentType::Matrix2of5 => "Matrix 2 of 5",
+            ContentType::Maxicode => "MaxiCode",
+            ContentType::MicroPdf => "MicroPDF417",
+            ContentType::MicroPdfCca => "MicroPDF CCA",
+            ContentType::MicroQr => "Micro QR",
+            ContentType::Msi => "MSI",
+            ContentType::Multicode => "Multicode",
+            ContentType::Multipacket => "Multipacket",
+            ContentType::Nw7 => "NW-7",
+            ContentType::OcrB => "OCR-B",
+            ContentType::Pdf417 => "PDF417",
+            ContentType::PlanetUs => "US Planet",
+            ContentType::PostalAus => "Australia Post",
+            ContentType::PostalNl => "Netherlands KIX Code",
+            ContentType::PostalJp => "Japan Post",
+            ContentType::PostalUk => "UK Postal",
+            ContentType::PostbarCa => "Canadian Postbar",
+            ContentType::PostnetUs => "US Postnet",
+            ContentType::Qr => "QR Code",
+            ContentType::RfidRaw => "RFID Raw",
+            ContentType::RfidURI => "RFID URI",
+            ContentType::RssExpandedCoupon => "GS1 DataBar Expanded Coupon",
+            ContentType::ScanletWebcode => "Scanlet Webcode",
+            ContentType::Signature => "Signature",
+            ContentType::Telepen => "Telepen",
+            ContentType::Tlc39 => "TLC-39",
+            ContentType::Trioptic => "Trioptic Code 39",
+            ContentType::UdiParsed => "UDI Parsed",
+            ContentType::UpcA => "UPC-A",
+            ContentType::UpcAPlus2 => "UPC-A +2",
+            ContentType::UpcAPlus5 => "UPC-A +5",
+            ContentType::UpcE => "UPC-E",
+            ContentType::UpcEPlus2 => "UPC-E +2",
+            ContentType::UpcEPlus5 => "UPC-E +5",
+            ContentType::UpcE1 => "UPC-E1",
+            ContentType::UpcE1Plus2 => "UPC-E1 +2",
+            ContentType::UpcE1Plus5 => "UPC-E1 +5",
+            ContentType::UkPlessy => "UK Plessey",
+            ContentType::FourStateUs => "USPS 4CB",
+            ContentType::FourStateUs4 => "US 4-State FICS",
+        }
+    }
+
+    /// The ISO/IEC 15424 AIM symbology identifier
+    ///
+    /// Uses the modifier for the scanner's default settings, e.g. check
+    /// digits not being transmitted. Symbologies without an assigned
+    /// identifier return `None`.
+    pub fn aim_id(&self) -> Option<&'static str> {
+        let id = match self {
+            ContentType::Aztec | ContentType::AztecRune => "]z0",
+            ContentType::Bookland | ContentType::Issn => "]E0",
+            ContentType::Codabar | ContentType::Nw7 => "]F0",
+            ContentType::Code11 => "]H0",
+            ContentType::Code128 | ContentType::Isbt128 => "]C0",
+            ContentType::Isbt128Concat => "]C4",
+            ContentType::Code16K => "]K0",
+            ContentType::Code39 | ContentType::Trioptic => "]A0",
+            ContentType::Code39Ascii => "]A4",
+            ContentType::Code49 => "]T0",
+            ContentType::Code93 => "]G0",
+            ContentType::DataMatrix => "]d1",
+            ContentType::Gs1DataMatrix => "]d2",
+            ContentType::Discrete2of5 => "]S0",
+            ContentType::Dotcode => "]J0",
+            ContentType::Ean13 | ContentType::UpcA => "]E0",
+            ContentType::UpcE | ContentType::UpcE1 => "]E0",
+            ContentType::Ean8 => "]E4",
+            ContentType::Ean13Plus2
+            | ContentType::Ean13Plus5
+            | ContentType::Ean8Plus2
+            | ContentType::Ean8Plus5
+            | ContentType::UpcAPlus2
+            | ContentType::UpcAPlus5
+            | ContentType::UpcEPlus2
+            | ContentType::UpcEPlus5
+            | ContentType::UpcE1Plus2
+            | ContentType::UpcE1Plus5 => "]E3",
+            ContentType::GridMatrix => "]g0",
+            ContentType::Gs1_128 => "]C1",
+            ContentType::Gs1DataBar14
+            | ContentType::Gs1DataBarLimited
+            | ContentType::Gs1DataBarExpanded
+            | ContentType::RssExpandedCoupon => "]e0",
+            ContentType::Qr | ContentType::MacroQr | ContentType::MicroQr => {
+                "]Q1"
+            }
+            ContentType::Gs1Qr => "]Q3",
+            ContentType::HanXin => "]h0",
+            ContentType::Iata => "]R0",
+            ContentType::Interleaved2of5 => "]I0",
+            ContentType::Maxicode => "]U0",
+            ContentType::Msi => "]M0",
+            ContentType::Pdf417
+            | ContentType::MacroPdf417
+            | ContentType::MicroPdf
+            | ContentType::MacroMicroPdf => "]L0",
+            ContentType::Telepen => "]B0",
+            ContentType::UkPlessy => "]P0",
+            ContentType::OcrB => "]o3",
+            // Identified as "other symbology" by AIM
+            ContentType::Chinese2of5
+            | ContentType::Code32
+            | ContentType::Korean3of5
+            | ContentType::Matrix2of5
+            | ContentType::Tlc39 => "]X0",
+            ContentType::Coupon
+            | ContentType::CueCat
+            | ContentType::FrenchLottery
+            | ContentType::Mailmark
+            | ContentType::MicroPdfCca
+            | ContentType::Multicode
+            | ContentType::Multipacket
+            | ContentType::PlanetUs
+            | ContentType::PostalAus
+            | ContentType::PostalNl
+            | ContentType::PostalJp
+            | ContentType::PostalUk
+            | ContentType::PostbarCa
+            | ContentType::PostnetUs
+            | ContentType::RfidRaw
+            | ContentType::RfidURI
+            | ContentType::ScanletWebcode
+            | ContentType::Signature
+            | ContentType::UdiParsed
+            | ContentType::FourStateUs
+            | ContentType::FourStateUs4 => return None,
+        };
+
+        Some(id)
+    }
+
+    /// How the symbology lays out its modules
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            ContentType::Bookland
+            | ContentType::Chinese2of5
+            | ContentType::Codabar
+            | ContentType::Code11
+            | ContentType::Code128
+            | ContentType::Code32
+            | ContentType::Code39
+            | ContentType::Code39Ascii
+            | ContentType::Code93
+            | ContentType::Coupon
+            | ContentType::CueCat
+            | ContentType::Discrete2of5
+            | ContentType::Ean13
+            | ContentType::Ean13Plus2
+            | ContentType::Ean13Plus5
+            | ContentType::Ean8
+            | ContentType::Ean8Plus2
+            | ContentType::Ean8Plus5
+            | ContentType::FrenchLottery
+            | ContentType::Gs1_128
+            | ContentType::Gs1DataBarExpanded
+            | ContentType::Gs1DataBarLimited
+            | ContentType::Gs1DataBar14
+            | ContentType::Iata
+            | ContentType::Isbt128
+            | ContentType::Isbt128Concat
+            | ContentType::Issn
+            | ContentType::Interleaved2of5
+            | ContentType::Korean3of5
+            | ContentType::Matrix2of5
+            | ContentType::Msi
+            | ContentType::Nw7
+            | ContentType::RssExpandedCoupon
+            | ContentType::Telepen
+            | ContentType::Tlc39
+            | ContentType::Trioptic
+            | ContentType::UpcA
+            | ContentType::UpcAPlus2
+            | ContentType::UpcAPlus5
+            | ContentType::UpcE
+            | ContentType::UpcEPlus2
+            | ContentType::UpcEPlus5
+            | ContentType::UpcE1
+            | ContentType::UpcE1Plus2
+            | ContentType::UpcE1Plus5
+            | ContentType::UkPlessy => Dimension::Linear,
+            ContentType::Code16K
+            | ContentType::Code49
+            | ContentType::MacroMicroPdf
+            | ContentType::MacroPdf417
+            | ContentType::MicroPdf
+            | ContentType::Pdf417 => Dimension::Stacked,
+            ContentType::Aztec
+            | ContentType::AztecRune
+            | ContentType::DataMatrix
+            | ContentType::Dotcode
+            | ContentType::GridMatrix
+            | ContentType::Gs1DataMatrix
+            | ContentType::Gs1Qr
+            | ContentType::HanXin
+            | ContentType::MacroQr
+            | ContentType::Maxicode
+            | ContentType::MicroQr
+            | ContentType::Qr
+            | ContentType::ScanletWebcode => Dimension::Matrix,
+            ContentType::Mailmark
+            | ContentType::PlanetUs
+            | ContentType::PostalAus
+            | ContentType::PostalNl
+            | ContentType::PostalJp
+            | ContentType::PostalUk
+            | ContentType::PostbarCa
+            | ContentType::PostnetUs
+            | ContentType::FourStateUs
+            | ContentType::FourStateUs4 => Dimension::Postal,
+            ContentType::MicroPdfCca => Dimension::Composite,
+            ContentType::Multicode
+            | ContentType::Multipacket
+            | ContentType::OcrB
+            | ContentType::RfidRaw
+            | ContentType::RfidURI
+            | ContentType::Signature
+            | ContentType::UdiParsed => Dimension::Other,
+        }
+    }
+
+    /// The group of related symbologies this one belongs to
+    pub fn family(&self) -> Family {
+        match self {
+            ContentType::Bookland
+            | ContentType::Ean13
+            | ContentType::Ean13Plus2
+            | ContentType::Ean13Plus5
+            | ContentType::Ean8
+            | ContentType::Ean8Plus2
+            | ContentType::Ean8Plus5
+            | ContentType::Issn
+            | ContentType::UpcA
+            | ContentType::UpcAPlus2
+            | ContentType::UpcAPlus5
+            | ContentType::UpcE
+            | ContentType::UpcEPlus2
+            | ContentType::UpcEPlus5
+            | ContentType::UpcE1
+            | ContentType::UpcE1Plus2
+            | ContentType::UpcE1Plus5 => Family::UpcEan,
+            ContentType::Coupon
+            | ContentType::Gs1_128
+            | ContentType::Gs1DataBarExpanded
+            | ContentType::Gs1DataBarLimited
+            | ContentType::Gs1DataBar14
+            | ContentType::Gs1DataMatrix
+            | ContentType::Gs1Qr
+            | ContentType::RssExpandedCoupon => Family::Gs1,
+            ContentType::Code128
+            | ContentType::Isbt128
+            | ContentType::Isbt128Concat => Family::Code128,
+            ContentType::Code32
+            | ContentType::Code39
+            | ContentType::Code39Ascii
+            | ContentType::Tlc39
+            | ContentType::Trioptic => Family::Code39,
+            ContentType::Chinese2of5
+            | ContentType::Discrete2of5
+            | ContentType::Iata
+            | ContentType::Interleaved2of5
+            | ContentType::Korean3of5
+            | ContentType::Matrix2of5 => Family::TwoOf5,
+            ContentType::MacroMicroPdf
+            | ContentType::MacroPdf417
+            | ContentType::MicroPdf
+            | ContentType::MicroPdfCca
+            | ContentType::Pdf417 => Family::Pdf417,
+            ContentType::MacroQr | ContentType::MicroQr | ContentType::Qr => {
+                Family::Qr
+            }
+            ContentType::Mailmark
+            | ContentType::PlanetUs
+            | ContentType::PostalAus
+            | ContentType::PostalNl
+            | ContentType::PostalJp
+            | ContentType::PostalUk
+            | ContentType::PostbarCa
+            | ContentType::PostnetUs
+            | ContentType::FourStateUs
+            | ContentType::FourStateUs4 => Family::Postal,
+            ContentType::RfidRaw | ContentType::RfidURI => Family::Rfid,
+            ContentType::Aztec
+            | ContentType::AztecRune
+            | ContentType::Codabar
+            | ContentType::Code11
+            | ContentType::Code16K
+            | ContentType::Code49
+            | ContentType::Code93
+            | ContentType::CueCat
+            | ContentType::DataMatrix
+            | ContentType::Dotcode
+            | ContentType::FrenchLottery
+            | ContentType::GridMatrix
+            | ContentType::HanXin
+            | ContentType::Maxicode
+            | ContentType::Msi
+            | ContentType::Multicode
+            | ContentType::Multipacket
+            | ContentType::Nw7
+            | ContentType::OcrB
+            | ContentType::ScanletWebcode
+            | ContentType::Signature
+            | ContentType::Telepen
+            | ContentType::UdiParsed
+            | ContentType::UkPlessy => Family::Other,
+        }
+    }
+}
+
+/// How a symbology lays out its modules, see `ContentType::dimension`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    /// A single row of bars
+    Linear,
+    /// Rows of linear symbols stacked on top of each other, like PDF417
+    Stacked,
+    /// A two dimensional grid, like QR or Data Matrix
+    Matrix,
+    /// Bars of varying height, as used by postal services
+    Postal,
+    /// The stacked component of a composite symbol, printed on top of a
+    /// linear one
+    Composite,
+    /// Not a symbol, like RFID tags, OCR or signature captures
+    Other,
 }
 
-fn decode(message: &[u8]) -> Result<RawMessage, DecodeError> {
+/// Group of related symbologies, see `ContentType::family`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Family {
+    /// UPC, EAN and what is encoded as EAN-13, like Bookland and ISSN
+    UpcEan,
+    /// Symbologies carrying GS1 element strings, see `symbology::gs1`
+    Gs1,
+    Code128,
+    Code39,
+    TwoOf5,
+    Pdf417,
+    Qr,
+    Postal,
+    Rfid,
+    Other,
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Decodes a single frame with the default `SsiConfig`
+pub fn decode(message: &[u8]) -> Result<RawMessage<'_>, DecodeError> {
+    decode_with(message, &SsiConfig::default())
+}
+
+/// Decodes a single frame according to `config`
+pub fn decode_with<'a>(
+    message: &'a [u8],
+    config: &SsiConfig,
+) -> Result<RawMessage<'a>, DecodeError> {
     let [length, payload @ .., checksum1, checksum2] = message else {
-        return Err(DecodeError::InvalidMessageLength);
+        return Err(DecodeError::InvalidMessageLength { len: message.len() });
     };
+    // The length byte counts itself and the payload, so it never wraps:
+    // longer messages are split into packets, and a length of 0 is as
+    // invalid as any other below the minimum of 4
+    if usize::from(*length) != message.len() - 2 {
+        return Err(DecodeError::InvalidMessageLength { len: message.len() });
+    }
 
     // Integrity check
-    let checksum = -i16::from_be_bytes([*checksum1, *checksum2]) as u16;
+    let format = config.checksum;
     let sum: u16 = calc_checksum(*length, payload);
+    let checksum_failed = !format.matches(sum, [*checksum1, *checksum2]);
 
-    if sum != checksum {
-        return Err(DecodeError::InvalidChecksum);
+    if checksum_failed && config.checksum_policy == ChecksumPolicy::Strict {
+        return Err(DecodeError::InvalidChecksum {
+            expected: format.read([*checksum1, *checksum2]),
+            computed: format.read(format.encode(sum)),
+            frame: message.to_vec(),
+        });
     }
 
     let [opcode, source, status, data @ ..] = payload else {
-        return Err(DecodeError::InvalidMessageLength);
+        return Err(DecodeError::InvalidMessageLength { len: message.len() });
     };
 
+    let opcode = OpCode::from(opcode);
+    if let (OpCode::DecodeData, []) = (&opcode, data) {
+        return Err(DecodeError::EmptyDecodeData);
+    }
+
+    let source = Source::from(source);
+    // Bits the crate doesn't know are kept, see `Status::unknown_bits`
+    let status = Status::from_bits_retain(*status);
+    if config.validate_frames && !is_consistent(opcode, source, status) {
+        return Err(DecodeError::InconsistentFrame { opcode, source });
+    }
+
     Ok(RawMessage {
         length: *length,
-        opcode: opcode.into(),
-        source: source.into(),
-        // Truncation ignores unknown bits
-        status: Status::from_bits_truncate(*status),
+        opcode,
+        source,
+        status,
         data,
+        checksum_failed,
+        was_retransmit: status.contains(Status::Retransmit),
     })
 }
 
-fn wrap(data: Vec<u8>) -> Vec<u8> {
+/// Decodes every complete frame in `buffer`
+///
+/// Frames are cut from `buffer` by a `Framer`, as on a live port, so
+/// garbage between frames is skipped the same way. Decoding stops at the
+/// first frame that isn't complete yet, and the returned remainder starts
+/// there: it is empty if `buffer` ends on a frame boundary, and otherwise
+/// holds what may be the start of the next frame, to be prepended to the
+/// bytes read next. A frame failing to decode doesn't stop decoding.
+///
+/// Uses the default `SsiConfig`.
+pub fn decode_all(
+    buffer: &[u8],
+) -> (Vec<Result<OwnedMessage, DecodeError>>, &[u8]) {
+    let config = SsiConfig::default();
+    let mut framer = Framer::new(config.clone());
+    framer.push(buffer);
+
+    let mut results = Vec::new();
+    while let Some(frame) = framer.next_frame() {
+        results.push(frame.and_then(|frame| {
+            decode_with(&frame, &config).map(|message| message.to_owned())
+        }));
+    }
+    // The framer only ever drops bytes from the front
+    let rest = &buffer[buffer.len() - framer.buffered().len()..];
+
+    (results, rest)
+}
+
+/// Checks a frame against combinations the protocol rules out
+fn is_consistent(opcode: OpCode, source: Source, status: Status) -> bool {
+    match (opcode, source) {
+        (_, Source::Other(_)) => false,
+        // Only scanners decode or take pictures
+        (
+            OpCode::DecodeData | OpCode::ImageData | OpCode::VideoData,
+            Source::Host,
+        ) => false,
+        // Handshake frames are never split up
+        (OpCode::Ack | OpCode::Nack, _) => {
+            !status.contains(Status::Continuation)
+        }
+        _ => true,
+    }
+}
+
+/// Most bytes a frame carries after its length byte, opcode, source and
+/// status included, as the length byte counts itself
+pub const MAX_PAYLOAD: usize = u8::MAX as usize - 1;
+
+/// The frame carrying `data` (opcode, source, status and data bytes)
+///
+/// Fails with `SsiError::PayloadTooLong` if `data` exceeds `MAX_PAYLOAD`,
+/// longer messages need splitting into packets by the caller.
+pub fn wrap(data: Vec<u8>) -> Result<Vec<u8>, SsiError> {
+    let mut output = Vec::with_capacity(data.len() + 3);
+    wrap_into(&mut output, &data)?;
+
+    Ok(output)
+}
+
+/// Like `wrap`, but reuses `buf` instead of allocating a new frame
+pub fn wrap_into(buf: &mut Vec<u8>, data: &[u8]) -> Result<(), SsiError> {
+    wrap_into_with(buf, data, ChecksumFormat::default())
+}
+
+fn wrap_into_with(
+    buf: &mut Vec<u8>,
+    data: &[u8],
+    format: ChecksumFormat,
+) -> Result<(), SsiError> {
+    if data.len() > MAX_PAYLOAD {
+        return Err(SsiError::PayloadTooLong { len: data.len() });
+    }
+
     // Size counts the size itself
     let size = data.len() as u8 + 1;
     // Checksum includes the size
-    let checksum = calc_checksum(size, &data);
+    let checksum = calc_checksum(size, data);
+
+    buf.clear();
+    buf.push(size);
+    buf.extend_from_slice(data);
+    buf.extend(format.encode(checksum));
 
-    let mut output = vec![size];
-    output.extend(data);
-    output.extend((-(checksum as i16)).to_be_bytes());
+    Ok(())
+}
+
+/// Finds the checksum convention `message` was sent with, other than `format`
+///
+/// Helps telling a corrupted frame from a scanner that uses another
+/// convention after `decode` reported `DecodeError::InvalidChecksum`.
+fn diagnose_checksum(
+    message: &[u8],
+    format: ChecksumFormat,
+) -> Option<ChecksumFormat> {
+    let [length, payload @ .., checksum1, checksum2] = message else {
+        return None;
+    };
+    let sum = calc_checksum(*length, payload);
 
-    output
+    ChecksumFormat::ALL.into_iter().find(|alternative| {
+        *alternative != format
+            && alternative.matches(sum, [*checksum1, *checksum2])
+    })
 }
 
-pub async fn run(port_name: &str, baud_rate: u32) {
-    let port = serialport::new(port_name, baud_rate)
-        .timeout(Duration::from_millis(10))
-        .open();
+/// Appended to every scan in keyboard wedge output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Terminator {
+    None,
+    #[default]
+    Lf,
+    CrLf,
+    Tab,
+}
 
-    let mut port = match port {
-        Ok(port) => port,
-        Err(e) => {
-            eprintln!("Failed to open \"{}\". Error: {}", port_name, e);
-            ::std::process::exit(1);
+impl Terminator {
+    pub fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Terminator::None => b"",
+            Terminator::Lf => b"\n",
+            Terminator::CrLf => b"\r\n",
+            Terminator::Tab => b"\t",
         }
-    };
+    }
+}
+
+impl FromStr for Terminator {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "none" => Ok(Terminator::None),
+            "lf" => Ok(Terminator::Lf),
+            "crlf" => Ok(Terminator::CrLf),
+            "tab" => Ok(Terminator::Tab),
+            _ => Err("Expected one of none, lf, crlf or tab"),
+        }
+    }
+}
+
+/// How scan content is turned into text for output
+///
+/// Scanners transmit label content as it was encoded, which is not
+/// necessarily UTF-8: labels in ISO 8859-1 are common, and GS1 or RFID
+/// content can be binary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// UTF-8, with invalid sequences replaced
+    #[default]
+    Utf8,
+    /// ISO 8859-1, where every byte is a character
+    Latin1,
+    /// No conversion, the bytes are passed on untouched
+    Raw,
+}
+
+impl TextEncoding {
+    /// Converts `content`, `None` for `Raw`
+    pub fn decode(self, content: &[u8]) -> Option<Cow<'_, str>> {
+        match self {
+            TextEncoding::Utf8 => Some(String::from_utf8_lossy(content)),
+            TextEncoding::Latin1 => {
+                Some(content.iter().copied().map(char::from).collect())
+            }
+            TextEncoding::Raw => None,
+        }
+    }
+}
+
+impl FromStr for TextEncoding {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "utf8" => Ok(TextEncoding::Utf8),
+            "latin1" => Ok(TextEncoding::Latin1),
+            "raw" => Ok(TextEncoding::Raw),
+            _ => Err("Expected one of utf8, latin1 or raw"),
+        }
+    }
+}
+
+/// Settings for `run`
+#[derive(Debug, Clone)]
+pub struct SsiConfig {
+    /// Checksum convention for received and sent frames
+    pub checksum: ChecksumFormat,
+    /// Whether to reject frames with a checksum mismatch
+    pub checksum_policy: ChecksumPolicy,
+    /// Only report opcode and raw data of every packet as it arrives,
+    /// skipping reassembly, the symbology lookup and text conversion
+    pub quiet_decode: bool,
+    /// Print nothing but the decoded data of each scan followed by the
+    /// terminator, like a keyboard wedge scanner would type it
+    pub wedge: Option<Terminator>,
+    /// Reject frames whose opcode, source and status contradict each other
+    /// with `DecodeError::InconsistentFrame`, catching desynchronized frames
+    /// that happen to pass the checksum. Considered inconsistent are:
+    ///
+    /// - any source other than scanner or host
+    /// - `DecodeData` from the host
+    /// - ACK or NACK with the continuation bit set
+    pub validate_frames: bool,
+    /// Pause before every frame sent by `Scanner::send` and the command
+    /// helpers built on it, for scanners dropping commands that follow each
+    /// other too closely
+    pub command_delay: Duration,
+    /// Code ID transmission the scanner is set to, telling where the code
+    /// ID ends and the content starts, see `ScanData`
+    pub code_id: CodeId,
+    /// Conversion of scan content for printing. Wedge output is always
+    /// the untouched content.
+    pub encoding: TextEncoding,
+    /// Hex dump messages with an unknown opcode or content type, for
+    /// finding out what a scanner not supported yet sends
+    pub hex_dump: bool,
+    /// Record every frame to this file, see `Capture`
+    pub capture: Option<PathBuf>,
+    /// Size at which the capture file is rotated
+    pub capture_max_size: u64,
+    /// Ping the scanner this often while waiting for scans, reporting when
+    /// it stops answering within the interval
+    pub keepalive: Option<Duration>,
+    /// Flush the scanner's queue after this many checksum errors without a
+    /// scan decoding in between, assuming the line garbled everything the
+    /// scanner had queued. Scans still in the queue are lost.
+    pub flush_after: Option<u32>,
+    /// ACK the scanner's frames as the protocol requires. Disable to listen
+    /// in on a line where another host does the handshake: `run` then
+    /// writes nothing to the port, which also rules out `keepalive` and
+    /// `flush_after`.
+    pub auto_ack: bool,
+    /// Source byte of the host's ACKs, `Source::Host` unless a protocol
+    /// converter in between expects something else
+    pub ack_source: Source,
+    /// How long a single read of the port blocks. Longer timeouts spare the
+    /// CPU while waiting for scans, shorter ones make keepalive pings and
+    /// command timeouts more precise.
+    pub read_timeout: Duration,
+    /// Makes `run`, `Scanner::dispatch` and `Scanner::into_stream` stop
+    /// once `true` is sent, like on Ctrl-C. Checked between reads, so it
+    /// takes effect within `read_timeout`.
+    pub shutdown: Option<watch::Receiver<bool>>,
+}
+
+impl Default for SsiConfig {
+    fn default() -> SsiConfig {
+        SsiConfig {
+            checksum: ChecksumFormat::default(),
+            checksum_policy: ChecksumPolicy::default(),
+            quiet_decode: false,
+            wedge: None,
+            validate_frames: false,
+            command_delay: Duration::ZERO,
+            code_id: CodeId::None,
+            encoding: TextEncoding::default(),
+            hex_dump: false,
+            keepalive: None,
+            flush_after: None,
+            capture: None,
+            capture_max_size: 16 * 1024 * 1024,
+            auto_ack: true,
+            ack_source: Source::Host,
+            read_timeout: READ_TIMEOUT,
+            shutdown: None,
+        }
+    }
+}
 
-    println!("Receiving data on {} at {} baud:", &port_name, &baud_rate);
+/// How many received frames `message_stream` keeps before pausing reading
+const STREAM_CAPACITY: usize = 16;
+
+/// Opens the serial port and receives every frame on the returned channel
+///
+/// See `Scanner::into_stream`, which this sets up with default capacity.
+pub fn message_stream(
+    port_name: &str,
+    baud_rate: u32,
+    config: SsiConfig,
+) -> Result<mpsc::Receiver<Result<OwnedMessage, SsiError>>, SsiError> {
+    let scanner = Scanner::open(port_name, baud_rate, config)?;
+
+    Ok(scanner.into_stream(STREAM_CAPACITY))
+}
+
+/// Prints everything the scanner sends until reading from or writing to
+/// the port fails, or until `SsiConfig::shutdown`
+///
+/// Read timeouts and frames that fail to decode are not errors, they are
+/// reported and reading goes on. See `PrintHandler` for the output.
+pub async fn run(
+    port_name: &str,
+    baud_rate: u32,
+    config: SsiConfig,
+) -> Result<(), SsiError> {
+    let mut scanner = Scanner::open(port_name, baud_rate, config.clone())?;
+    print_nacks(&mut scanner);
+
+    if config.wedge.is_none() {
+        println!("Receiving data on {} at {} baud:", &port_name, &baud_rate);
+    }
+
+    serve(scanner, PrintHandler::new(config))
+}
+
+/// `run` for a scanner behind a serial to Ethernet converter, see
+/// `Scanner::connect_tcp`
+pub async fn run_tcp(addr: &str, config: SsiConfig) -> Result<(), SsiError> {
+    let mut scanner = Scanner::connect_tcp(addr, config.clone())?;
+    print_nacks(&mut scanner);
+
+    if config.wedge.is_none() {
+        println!("Receiving data from {addr}:");
+    }
+
+    serve(scanner, PrintHandler::new(config))
+}
+
+fn print_nacks<T: io::Read + io::Write>(scanner: &mut Scanner<T>) {
+    scanner.on_line_event(|event| {
+        if let LineEvent::Nack {
+            opcode,
+            attempt,
+            cause: Some(cause),
+            ..
+        } = event
+        {
+            eprintln!("{opcode} NACKed on attempt {attempt}: {cause:?}");
+        }
+    });
+}
+
+/// Hands everything the scanner sends to `handler` until reading from or
+/// writing to the port fails, or until `SsiConfig::shutdown`
+///
+/// Like `run`, with the output left to `handler`.
+pub async fn run_with<H: ScanHandler>(
+    port_name: &str,
+    baud_rate: u32,
+    config: SsiConfig,
+    handler: H,
+) -> Result<(), SsiError> {
+    let scanner = Scanner::open(port_name, baud_rate, config)?;
+    serve(scanner, handler)
+}
+
+/// The receive loop of `run`, `run_tcp` and `run_with`
+///
+/// Works on any port, the framing, decoding and handshake don't care
+/// what carries the bytes.
+fn serve<T: io::Read + io::Write>(
+    mut scanner: Scanner<T>,
+    mut handler: impl ScanHandler,
+) -> Result<(), SsiError> {
+    let config = scanner.config.clone();
+    if let Some(path) = &config.capture {
+        scanner.capture_to(Capture::create(path, config.capture_max_size)?);
+    }
 
     let mut serial_buf: Vec<u8> = vec![0; 1000];
+    let mut last_ping = Instant::now();
+    let mut ping_pending = false;
+    let mut checksum_errors = 0;
     loop {
-        match port.read(serial_buf.as_mut_slice()) {
-            Ok(t) => {
-                // TODO: Check length of t
-                // TODO: Investigate #[repr(C, packed)] to unpack into struct
-                let message = &serial_buf[..t];
-                let response = decode(message);
-
-                match response {
-                    Ok(RawMessage {
-                        length,
-                        opcode,
-                        source,
-                        status,
-                        data,
-                    }) => {
-                        let ack = wrap(vec![
-                            OpCode::Ack.into(),
-                            Source::Host.into(),
-                            Status::default().into(),
-                        ]);
-                        port.write(&ack).unwrap();
-
-                        println!("Length: {length}");
-                        println!("Opcode: {opcode:?}");
-                        println!("Source: {source:?}");
-                        println!("Status: {status:?}");
-
-                        if let OpCode::DecodeData = opcode {
-                            if let [content_type, content @ ..] = data {
-                                match <ContentType as TryFrom<u8>>::try_from(
-                                    *content_type,
-                                ) {
-                                    Ok(content_type) => {
-                                        println!("Type: '{:?}'", content_type);
-                                    }
-                                    Err(_) => {
-                                        println!(
-                                            "Unknown type: '{:#04x}'",
-                                            content_type
-                                        );
-                                    }
-                                }
-
-                                let decoded = String::from_utf8_lossy(content);
-                                println!("Decoded msg: '{}'", decoded);
-                            } else {
-                                println!("Invalid DecodeData");
-                            };
-                        }
-                    }
-                    Err(decode_error) => {
-                        println!("Error decoding data: {decode_error:?}");
-                    }
-                };
+        if scanner.is_shut_down() {
+            return Ok(());
+        }
+
+        if let Some(interval) = config.keepalive.filter(|_| config.auto_ack) {
+            if last_ping.elapsed() >= interval {
+                if ping_pending {
+                    handler.on_unresponsive();
+                }
+                scanner.send(&revision_request())?;
+                ping_pending = true;
+                last_ping = Instant::now();
             }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => (),
-            Err(e) => eprintln!("{:?}", e),
         }
+
+        let response = match scanner.read_frame(serial_buf.as_mut_slice()) {
+            Ok(Some(t)) => decode_with(&serial_buf[..t], &config),
+            Ok(None) => continue,
+            // Rejected by the framer, which already skipped ahead
+            Err(SsiError::Decode(e)) => Err(e),
+            Err(e) => return Err(e),
+        };
+
+        // TODO: Investigate #[repr(C, packed)] to unpack into struct
+        match response {
+            Ok(message) => {
+                match scanner.handshake(
+                    &message.opcode,
+                    &message.status,
+                    message.data,
+                ) {
+                    Ok(()) => (),
+                    // The port is gone, nothing more to read
+                    Err(e @ SsiError::Io(_)) => return Err(e),
+                    Err(e) => handler.on_handshake_error(&e),
+                }
+
+                if message.opcode == OpCode::DecodeData {
+                    checksum_errors = 0;
+                }
+
+                // Keepalive replies are no news
+                if message.opcode == OpCode::ReplyRevision && ping_pending {
+                    ping_pending = false;
+                    continue;
+                }
+
+                handler.on_message(&message);
+            }
+            Err(decode_error) => {
+                handler.on_error(&decode_error);
+
+                if let DecodeError::InvalidChecksum { .. } = decode_error {
+                    checksum_errors += 1;
+                }
+                if config.auto_ack
+                    && Some(checksum_errors) == config.flush_after
+                {
+                    checksum_errors = 0;
+                    // Start over with the next frame on both sides
+                    scanner.drain(Duration::ZERO)?;
+                    scanner.send(&bare_payload(OpCode::FlushQueue))?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn opcodes_round_trip() {
+        let mut bytes = HashSet::new();
+        let mut names = HashSet::new();
+        for &opcode in OpCode::all() {
+            let byte = u8::from(opcode);
+            assert_eq!(OpCode::from(&byte), opcode);
+            assert!(bytes.insert(byte), "{byte:#04x} used twice");
+            assert!(names.insert(opcode.name()), "{opcode} named twice");
+            assert_eq!(opcode.to_string(), opcode.name());
+            assert!(!matches!(opcode, OpCode::Other(_)));
+        }
+
+        // Every byte without a variant stays as it is
+        for byte in 0..=u8::MAX {
+            if !bytes.contains(&byte) {
+                assert_eq!(OpCode::from(&byte), OpCode::Other(byte));
+            }
+        }
+        assert_eq!(OpCode::Other(0xab).to_string(), "Other(0xab)");
+    }
+
+    #[test]
+    fn content_type_discriminants_match_bytes() {
+        for byte in 0..=u8::MAX {
+            if let Ok(content_type) = ContentType::try_from(byte) {
+                assert_eq!(content_type as u8, byte, "{content_type:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn asian_numeric_codes() {
+        assert_eq!(ContentType::try_from(0x72), Ok(ContentType::Chinese2of5));
+        assert_eq!(ContentType::try_from(0x73), Ok(ContentType::Korean3of5));
+    }
+
+    #[test]
+    fn matrix_2_of_5_round_trips() {
+        assert_eq!(ContentType::try_from(0x71), Ok(ContentType::Matrix2of5));
+        assert_eq!(ContentType::Matrix2of5 as u8, 0x71);
+    }
+
+    #[test]
+    fn decode_all_keeps_partial_frame() {
+        let frame =
+            wrap(vec![OpCode::DecodeData.into(), 0x00, 0x00, 0x03, b'x'])
+                .unwrap();
+        let buffer = [&frame[..], &[0x01, 0x02], &frame, &frame[..3]].concat();
+
+        let (results, rest) = decode_all(&buffer);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().data, [0x03, b'x']);
+        assert!(matches!(results[1], Err(DecodeError::InvalidLengthByte(1))));
+        assert_eq!(results[2].as_ref().unwrap().data, [0x03, b'x']);
+        assert_eq!(rest, &frame[..3]);
+    }
+
+    #[test]
+    fn wrap_at_max_payload() {
+        let mut payload = vec![OpCode::DecodeData.into(), 0x00, 0x00, 0x03];
+        payload.resize(MAX_PAYLOAD, b'x');
+        let frame = wrap(payload).unwrap();
+        assert_eq!(frame[0], 0xff);
+        assert_eq!(frame.len(), MAX_PAYLOAD + 3);
+        assert_eq!(decode(&frame).unwrap().data.len(), MAX_PAYLOAD - 3);
+
+        let mut payload = vec![OpCode::DecodeData.into(), 0x00, 0x00, 0x03];
+        payload.resize(MAX_PAYLOAD + 1, b'x');
+        assert!(matches!(
+            wrap(payload),
+            Err(SsiError::PayloadTooLong { len: 255 })
+        ));
     }
 }
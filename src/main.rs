@@ -1,18 +1,147 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use clap::Parser;
+use ssi::{CodeId, SsiConfig, SsiError, Terminator, TextEncoding};
+use tokio::sync::watch;
 
 #[derive(Parser, Clone)]
 #[command(version, about)]
 pub struct Args {
-    #[arg(help = "Serial port (as path to /dev/tty* or COM port)")]
-    port: String,
+    #[arg(
+        help = "Serial port (as path to /dev/tty* or COM port)",
+        required_unless_present = "transcript"
+    )]
+    port: Option<String>,
 
     #[arg(help = "Baud rate", default_value = "9600")]
     baud: u32,
+
+    #[arg(long, help = "Print only the decoded data, like a keyboard wedge")]
+    wedge: bool,
+
+    #[arg(
+        long,
+        help = "Terminator after each scan in wedge mode (none, lf, crlf, tab)",
+        default_value = "lf"
+    )]
+    terminator: Terminator,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Check every SECONDS that the scanner is still responding"
+    )]
+    keepalive: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Code ID the scanner transmits ahead of scans (none, symbol, aim)",
+        default_value = "none"
+    )]
+    code_id: CodeId,
+
+    #[arg(
+        long,
+        help = "Text encoding of printed scans (utf8, latin1, raw)",
+        default_value = "utf8"
+    )]
+    encoding: TextEncoding,
+
+    #[arg(
+        long,
+        help = "Hex dump messages with an unknown opcode or content type"
+    )]
+    hex: bool,
+
+    #[arg(
+        long,
+        help = "Never write to the port, for listening in on another host"
+    )]
+    no_ack: bool,
+
+    #[arg(long, help = "Record every frame to this file for later replay")]
+    capture: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Print a capture file as annotated transcript and exit"
+    )]
+    transcript: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Longest time a single read of the port blocks",
+        default_value = "10"
+    )]
+    timeout_ms: u64,
 }
 
 #[tokio::main]
 async fn main() {
-    let Args { port, baud } = Args::parse();
+    let Args {
+        port,
+        baud,
+        wedge,
+        terminator,
+        keepalive,
+        code_id,
+        encoding,
+        hex,
+        no_ack,
+        capture,
+        transcript,
+        timeout_ms,
+    } = Args::parse();
+
+    if let Some(path) = transcript {
+        match ssi::read_capture(&path) {
+            Ok(frames) => print!("{}", ssi::transcript(&frames)),
+            Err(e) => {
+                eprintln!(
+                    "Failed to read \"{}\". Error: {}",
+                    path.display(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let port = port.expect("clap requires the port without --transcript");
+
+    // The receive loop blocks this thread, so wait for Ctrl-C on another
+    let (shutdown_tx, shutdown) = watch::channel(false);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = shutdown_tx.send(true);
+        }
+    });
+
+    let config = SsiConfig {
+        wedge: wedge.then_some(terminator),
+        keepalive: keepalive.map(Duration::from_secs),
+        code_id,
+        encoding,
+        hex_dump: hex,
+        auto_ack: !no_ack,
+        capture,
+        read_timeout: Duration::from_millis(timeout_ms),
+        shutdown: Some(shutdown),
+        ..Default::default()
+    };
 
-    ssi::run(&port, baud).await;
+    match ssi::run(&port, baud, config).await {
+        Ok(()) => (),
+        Err(SsiError::Serial(e)) => {
+            eprintln!("Failed to open \"{}\". Error: {}", port, e);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,298 @@
+//! Scanner parameters
+//!
+//! Parameters are addressed by number and hold a byte each. On the wire,
+//! numbers below 0xf0 take a single byte, numbers up to 0x2ff take two,
+//! 0xf0 plus the upper byte followed by the lower byte, and every other
+//! number takes three bytes behind 0xf8.
+//!
+//! Not everything a scanner reports is a parameter. Cordless scanners keep
+//! battery charge and health as attributes of the Remote Scanner Management
+//! protocol, which `PARAM_REQUEST` doesn't reach, so there is no battery
+//! readout on top of `Scanner::get_params`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ContentType, DecodeError, OpCode, Source, SsiError, Status};
+
+/// Beep code in `PARAM_SEND` for not beeping on the change
+pub(crate) const NO_BEEP: u8 = 0xff;
+
+/// Number of a scanner parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ParamNumber(pub u16);
+
+impl ParamNumber {
+    /// High, medium or low (0 to 2)
+    pub const BEEPER_VOLUME: ParamNumber = ParamNumber(0x8c);
+    pub const BEEPER_TONE: ParamNumber = ParamNumber(0x91);
+    /// Level, pulse, continuous, blink or host triggered
+    pub const TRIGGER_MODE: ParamNumber = ParamNumber(0x8a);
+    /// Code ID character transmission
+    pub const CODE_ID: ParamNumber = ParamNumber(0x2d);
+    /// Which of prefix and suffixes surround the scan data
+    pub const SCAN_DATA_FORMAT: ParamNumber = ParamNumber(0xeb);
+    pub const PREFIX: ParamNumber = ParamNumber(0x69);
+    pub const SUFFIX1: ParamNumber = ParamNumber(0x68);
+    pub const SUFFIX2: ParamNumber = ParamNumber(0x6a);
+}
+
+impl ParamNumber {
+    /// The parameter enabling `content_type`, `None` where the crate
+    /// doesn't know it
+    ///
+    /// Variants like the Macro, GS1 or supplemental ones of a symbology
+    /// share its parameter.
+    pub fn enabling(content_type: ContentType) -> Option<ParamNumber> {
+        let param = match content_type {
+            ContentType::Code39 => 0x00,
+            ContentType::UpcA
+            | ContentType::UpcAPlus2
+            | ContentType::UpcAPlus5 => 0x01,
+            ContentType::UpcE
+            | ContentType::UpcEPlus2
+            | ContentType::UpcEPlus5 => 0x02,
+            ContentType::Ean13
+            | ContentType::Ean13Plus2
+            | ContentType::Ean13Plus5 => 0x03,
+            ContentType::Ean8
+            | ContentType::Ean8Plus2
+            | ContentType::Ean8Plus5 => 0x04,
+            ContentType::Discrete2of5 => 0x05,
+            ContentType::Interleaved2of5 => 0x06,
+            ContentType::Codabar | ContentType::Nw7 => 0x07,
+            ContentType::Code128 => 0x08,
+            ContentType::Code93 => 0x09,
+            ContentType::Code11 => 0x0a,
+            ContentType::Msi => 0x0b,
+            ContentType::UpcE1
+            | ContentType::UpcE1Plus2
+            | ContentType::UpcE1Plus5 => 0x0c,
+            ContentType::Trioptic => 0x0d,
+            ContentType::Gs1_128 => 0x0e,
+            ContentType::Pdf417 | ContentType::MacroPdf417 => 0x0f,
+            ContentType::Bookland => 0x53,
+            ContentType::Isbt128 | ContentType::Isbt128Concat => 0x54,
+            ContentType::MicroPdf | ContentType::MacroMicroPdf => 0xe3,
+            ContentType::DataMatrix | ContentType::Gs1DataMatrix => 0x124,
+            ContentType::Qr | ContentType::MacroQr | ContentType::Gs1Qr => {
+                0x125
+            }
+            ContentType::Maxicode => 0x126,
+            ContentType::Gs1DataBar14 => 0x152,
+            ContentType::Gs1DataBarLimited => 0x153,
+            ContentType::Gs1DataBarExpanded => 0x154,
+            ContentType::Chinese2of5 => 0x198,
+            ContentType::MicroQr => 0x23d,
+            ContentType::Aztec => 0x23e,
+            _ => return None,
+        };
+
+        Some(ParamNumber(param))
+    }
+}
+
+impl fmt::LowerHex for ParamNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// Appends the wire representation of parameter number `param`
+pub(crate) fn encode_param(buf: &mut Vec<u8>, ParamNumber(param): ParamNumber) {
+    match param {
+        0..=0xef => buf.push(param as u8),
+        0xf0..=0x2ff => {
+            buf.extend([0xf0 + (param >> 8) as u8, param as u8]);
+        }
+        _ => {
+            buf.push(0xf8);
+            buf.extend(param.to_be_bytes());
+        }
+    }
+}
+
+/// Splits the parameter number `data` starts with off the rest
+pub(crate) fn parse_param(data: &[u8]) -> Option<(ParamNumber, &[u8])> {
+    let (param, rest) = match data {
+        [prefix @ 0xf0..=0xf2, low, rest @ ..] => {
+            (u16::from_be_bytes([prefix - 0xf0, *low]), rest)
+        }
+        [0xf8, high, low, rest @ ..] => {
+            (u16::from_be_bytes([*high, *low]), rest)
+        }
+        [param @ 0..=0xef, rest @ ..] => (u16::from(*param), rest),
+        _ => return None,
+    };
+
+    Some((ParamNumber(param), rest))
+}
+
+/// Parses the data of a `ParamSend` into parameter/value pairs
+///
+/// The data starts with a beep code, followed by the pairs. Fails if a
+/// pair is cut short or a number uses an unknown escape.
+pub fn parse_param_send(
+    data: &[u8],
+) -> Result<Vec<(ParamNumber, u8)>, DecodeError> {
+    let truncated = || DecodeError::InvalidMessageLength { len: data.len() };
+    let Some((_beep_code, mut rest)) = data.split_first() else {
+        return Err(truncated());
+    };
+    let mut params = Vec::new();
+
+    while !rest.is_empty() {
+        let Some((param, [value, tail @ ..])) = parse_param(rest) else {
+            return Err(truncated());
+        };
+
+        params.push((param, *value));
+        rest = tail;
+    }
+
+    Ok(params)
+}
+
+/// Payload of a `PARAM_REQUEST` for `params`
+pub(crate) fn param_request_payload(params: &[ParamNumber]) -> Vec<u8> {
+    let mut payload = vec![
+        OpCode::ParamRequest.into(),
+        Source::Host.into(),
+        Status::default().into(),
+    ];
+    for param in params {
+        encode_param(&mut payload, *param);
+    }
+
+    payload
+}
+
+/// Status of a parameter change, which survives a power cycle if
+/// `permanent`
+fn change_type(permanent: bool) -> Status {
+    if permanent {
+        Status::ChangeType
+    } else {
+        Status::default()
+    }
+}
+
+/// Payload of a `PARAM_SEND` writing `params`, surviving a power cycle if
+/// `permanent`
+pub(crate) fn param_send_payload(
+    params: &[(ParamNumber, u8)],
+    permanent: bool,
+) -> Vec<u8> {
+    let mut payload = vec![
+        OpCode::ParamSend.into(),
+        Source::Host.into(),
+        change_type(permanent).into(),
+        NO_BEEP,
+    ];
+    for (param, value) in params {
+        encode_param(&mut payload, *param);
+        payload.push(*value);
+    }
+
+    payload
+}
+
+/// Payload of a `CHANGE_ALL_CODE_TYPES` enabling or disabling every
+/// symbology, surviving a power cycle if `permanent`
+pub(crate) fn change_all_code_types_payload(
+    enable: bool,
+    permanent: bool,
+) -> [u8; 4] {
+    [
+        OpCode::ChangeAllCodeTypes.into(),
+        Source::Host.into(),
+        change_type(permanent).into(),
+        u8::from(enable),
+    ]
+}
+
+/// The `PARAM_SEND` values enabling `types`, failing on the first one
+/// without a known parameter
+pub(crate) fn enable_params(
+    types: &[ContentType],
+) -> Result<Vec<(ParamNumber, u8)>, SsiError> {
+    let mut params: Vec<(ParamNumber, u8)> = Vec::new();
+    for content_type in types {
+        let param = ParamNumber::enabling(*content_type)
+            .ok_or(SsiError::UnsupportedSymbology(*content_type))?;
+        // Variants share parameters
+        if !params.contains(&(param, 1)) {
+            params.push((param, 1));
+        }
+    }
+
+    Ok(params)
+}
+
+/// Which code ID character precedes the scan data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodeId {
+    #[default]
+    None,
+    /// The `]` prefixed AIM symbology identifier
+    Aim,
+    /// Symbol's own single character identifier
+    Symbol,
+}
+
+impl FromStr for CodeId {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "none" => Ok(CodeId::None),
+            "symbol" => Ok(CodeId::Symbol),
+            "aim" => Ok(CodeId::Aim),
+            _ => Err("Expected one of none, symbol or aim"),
+        }
+    }
+}
+
+/// Prefix, suffixes and code ID of transmitted scan data
+///
+/// Each of prefix and suffixes is an ASCII character, transmitted if set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransmitFormat {
+    pub prefix: Option<u8>,
+    pub suffix1: Option<u8>,
+    pub suffix2: Option<u8>,
+    pub code_id: CodeId,
+}
+
+impl TransmitFormat {
+    /// The parameter values describing this format
+    ///
+    /// Characters of unset prefix or suffixes are left alone.
+    pub(crate) fn params(&self) -> Vec<(ParamNumber, u8)> {
+        let code_id = match self.code_id {
+            CodeId::None => 0,
+            CodeId::Symbol => 1,
+            CodeId::Aim => 2,
+        };
+        // Bit field of prefix, suffix 2 and suffix 1, in that order
+        let data_format = u8::from(self.prefix.is_some()) << 2
+            | u8::from(self.suffix2.is_some()) << 1
+            | u8::from(self.suffix1.is_some());
+
+        let mut params = vec![
+            (ParamNumber::CODE_ID, code_id),
+            (ParamNumber::SCAN_DATA_FORMAT, data_format),
+        ];
+        for (param, char) in [
+            (ParamNumber::PREFIX, self.prefix),
+            (ParamNumber::SUFFIX1, self.suffix1),
+            (ParamNumber::SUFFIX2, self.suffix2),
+        ] {
+            if let Some(char) = char {
+                params.push((param, char));
+            }
+        }
+
+        params
+    }
+}
@@ -0,0 +1,184 @@
+//! The host side of the SSI handshake
+//!
+//! Every frame but ACK and NACK is acknowledged by the receiver. After
+//! sending a frame, the host waits for the scanner's ACK and resends the
+//! frame on a NACK or when the ACK doesn't come, up to `MAX_RETRIES` times,
+//! with the `Retransmit` status bit set. Requests the scanner answers
+//! with a reply, like `REQUEST_REVISION`, are taken as acknowledged by the
+//! reply instead. Scanner frames keep coming in the meantime and are
+//! acknowledged as usual, including each packet of a multipacket message
+//! (announced by the `Continuation` status bit on all but the last packet).
+
+use crate::OpCode;
+
+/// How often a NACKed frame is sent again before giving up
+pub(crate) const MAX_RETRIES: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ProtocolState {
+    #[default]
+    Idle,
+    /// The host sent a frame with `opcode` and waits for the scanner to
+    /// acknowledge it
+    AwaitingAck { opcode: OpCode, retries: u8 },
+}
+
+/// What the host has to do in response to a received frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Nothing to send
+    None,
+    /// Acknowledge the frame
+    Ack,
+    /// Send the last host frame again
+    Resend,
+    /// The last host frame was NACKed `MAX_RETRIES` times
+    GiveUp,
+}
+
+/// The opcode the scanner answers `request` with in place of an ACK
+fn reply_to(request: OpCode) -> Option<OpCode> {
+    let reply = match request {
+        OpCode::RequestRevision => OpCode::ReplyRevision,
+        OpCode::ParamRequest => OpCode::ParamSend,
+        OpCode::CapabilitiesRequest => OpCode::CapabilitiesReply,
+        _ => return None,
+    };
+
+    Some(reply)
+}
+
+impl ProtocolState {
+    /// Transition for the host having sent a frame with `opcode`
+    pub(crate) fn sent(&mut self, opcode: OpCode) {
+        *self = ProtocolState::AwaitingAck { opcode, retries: 0 };
+    }
+
+    /// Transition for the last host frame getting NACKed or going
+    /// unanswered, which is resent up to `MAX_RETRIES` times
+    pub(crate) fn retry(&mut self) -> Action {
+        match *self {
+            ProtocolState::AwaitingAck { opcode, retries }
+                if retries < MAX_RETRIES =>
+            {
+                *self = ProtocolState::AwaitingAck {
+                    opcode,
+                    retries: retries + 1,
+                };
+                Action::Resend
+            }
+            ProtocolState::AwaitingAck { .. } => {
+                *self = ProtocolState::Idle;
+                Action::GiveUp
+            }
+            ProtocolState::Idle => Action::None,
+        }
+    }
+
+    /// Transition for a frame received from the scanner
+    ///
+    /// The packets of a multipacket message are acknowledged one by one, so
+    /// the continuation bit makes no difference here.
+    pub(crate) fn received(&mut self, opcode: &OpCode) -> Action {
+        match (*self, opcode) {
+            (ProtocolState::AwaitingAck { .. }, OpCode::Ack) => {
+                *self = ProtocolState::Idle;
+                Action::None
+            }
+            (ProtocolState::AwaitingAck { .. }, OpCode::Nack) => self.retry(),
+            // Unsolicited, nothing to acknowledge or resend
+            (_, OpCode::Ack | OpCode::Nack) => Action::None,
+            // The reply stands in for the ACK, and is acknowledged itself
+            (
+                ProtocolState::AwaitingAck {
+                    opcode: request, ..
+                },
+                opcode,
+            ) if reply_to(request) == Some(*opcode) => {
+                *self = ProtocolState::Idle;
+                Action::Ack
+            }
+            // Scanner frames in between don't affect a pending ACK
+            (_, _) => Action::Ack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receive(state: &mut ProtocolState, opcode: OpCode) -> Action {
+        state.received(&opcode)
+    }
+
+    #[test]
+    fn ack_completes_command() {
+        let mut state = ProtocolState::default();
+        state.sent(OpCode::Beep);
+        assert_eq!(receive(&mut state, OpCode::Ack), Action::None);
+        assert_eq!(state, ProtocolState::Idle);
+    }
+
+    #[test]
+    fn nack_resends_until_giving_up() {
+        let mut state = ProtocolState::default();
+        state.sent(OpCode::Beep);
+        for retries in 1..=MAX_RETRIES {
+            assert_eq!(receive(&mut state, OpCode::Nack), Action::Resend);
+            assert_eq!(
+                state,
+                ProtocolState::AwaitingAck {
+                    opcode: OpCode::Beep,
+                    retries
+                }
+            );
+        }
+        assert_eq!(receive(&mut state, OpCode::Nack), Action::GiveUp);
+        assert_eq!(state, ProtocolState::Idle);
+
+        // A late NACK has nothing left to resend
+        assert_eq!(receive(&mut state, OpCode::Nack), Action::None);
+    }
+
+    #[test]
+    fn reply_completes_request() {
+        let mut state = ProtocolState::default();
+        state.sent(OpCode::RequestRevision);
+        assert_eq!(receive(&mut state, OpCode::DecodeData), Action::Ack);
+        assert!(matches!(state, ProtocolState::AwaitingAck { .. }));
+        assert_eq!(receive(&mut state, OpCode::ReplyRevision), Action::Ack);
+        assert_eq!(state, ProtocolState::Idle);
+
+        state.sent(OpCode::ParamRequest);
+        assert_eq!(receive(&mut state, OpCode::ParamSend), Action::Ack);
+        assert_eq!(state, ProtocolState::Idle);
+    }
+
+    #[test]
+    fn packets_are_acked_while_command_pending() {
+        let mut state = ProtocolState::default();
+        assert_eq!(receive(&mut state, OpCode::DecodeData), Action::Ack);
+        assert_eq!(state, ProtocolState::Idle);
+
+        state.sent(OpCode::Beep);
+        assert_eq!(receive(&mut state, OpCode::ImageData), Action::Ack);
+        assert_eq!(receive(&mut state, OpCode::ImageData), Action::Ack);
+        assert_eq!(receive(&mut state, OpCode::Ack), Action::None);
+        assert_eq!(state, ProtocolState::Idle);
+    }
+
+    #[test]
+    fn unanswered_frame_is_retried() {
+        let mut state = ProtocolState::default();
+        assert_eq!(state.retry(), Action::None);
+
+        state.sent(OpCode::Beep);
+        for _ in 0..MAX_RETRIES {
+            assert_eq!(state.retry(), Action::Resend);
+        }
+        // Timeouts and NACKs count towards the same limit
+        assert_eq!(receive(&mut state, OpCode::Nack), Action::GiveUp);
+        assert_eq!(state, ProtocolState::Idle);
+    }
+}
@@ -0,0 +1,120 @@
+//! Parsers for the data of the scanner's replies
+
+use crate::{DecodeError, OpCode};
+
+/// The scanner's `CapabilitiesReply`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Bit field of the supported baud rates
+    pub baud_rates: u16,
+    /// Bit field of the supported parity, stop bit and flow control
+    /// settings
+    pub serial_options: u8,
+    /// Supported multipacket modes
+    pub multipacket: u8,
+    /// Host commands the scanner understands
+    pub opcodes: Vec<OpCode>,
+}
+
+/// Parses the data of a `CapabilitiesReply`
+///
+/// Everything after the fixed fields is taken as the list of supported
+/// opcodes, so bytes a model appends beyond that show up as
+/// `OpCode::Other` rather than failing the parse.
+pub fn parse_capabilities(data: &[u8]) -> Result<Capabilities, DecodeError> {
+    let [baud1, baud2, serial_options, multipacket, opcodes @ ..] = data else {
+        return Err(DecodeError::InvalidMessageLength { len: data.len() });
+    };
+
+    Ok(Capabilities {
+        baud_rates: u16::from_be_bytes([*baud1, *baud2]),
+        serial_options: *serial_options,
+        multipacket: *multipacket,
+        opcodes: opcodes.iter().map(OpCode::from).collect(),
+    })
+}
+
+/// The scanner's `ReplyRevision`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// The whole revision string
+    pub raw: String,
+    /// Software revision, the first field
+    pub software: String,
+    /// Board type, the second field if present
+    pub board: Option<String>,
+    /// Any further fields, like engine code and version
+    pub other: Vec<String>,
+}
+
+/// Parses the data of a `ReplyRevision`
+///
+/// The revision is ASCII with space separated fields. Some models pad it
+/// with spaces or NULs, which are trimmed.
+pub fn parse_revision(data: &[u8]) -> Revision {
+    let raw = String::from_utf8_lossy(data)
+        .trim_end_matches([' ', '\0'])
+        .to_owned();
+    let mut fields = raw.split_whitespace().map(str::to_owned);
+
+    Revision {
+        software: fields.next().unwrap_or_default(),
+        board: fields.next(),
+        other: fields.collect(),
+        raw,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode;
+
+    /// A `CAPABILITIES_REPLY` frame as it comes off the wire, with two
+    /// padding bytes behind the opcode list
+    const CAPABILITIES_FRAME: [u8; 21] = [
+        0x13, 0xd3, 0x00, 0x00, 0x00, 0x3f, 0x07, 0x01, 0xc4, 0xc5, 0xe4, 0xe5,
+        0xc6, 0xc7, 0xd4, 0xd0, 0xd1, 0x00, 0x00, 0xf7, 0x7f,
+    ];
+
+    #[test]
+    fn capabilities_reply() {
+        let message = decode(&CAPABILITIES_FRAME).unwrap();
+        assert_eq!(message.opcode, OpCode::CapabilitiesReply);
+
+        let capabilities = parse_capabilities(message.data).unwrap();
+        assert_eq!(capabilities.baud_rates, 0x003f);
+        assert_eq!(capabilities.serial_options, 0x07);
+        assert_eq!(capabilities.multipacket, 0x01);
+        assert_eq!(
+            capabilities.opcodes[..9],
+            [
+                OpCode::AimOff,
+                OpCode::AimOn,
+                OpCode::StartSession,
+                OpCode::StopSession,
+                OpCode::ParamSend,
+                OpCode::ParamRequest,
+                OpCode::CapabilitiesRequest,
+                OpCode::Ack,
+                OpCode::Nack,
+            ]
+        );
+        // Bytes beyond what the crate knows don't fail the parse
+        assert_eq!(capabilities.opcodes[9..], [OpCode::Other(0x00); 2]);
+    }
+
+    #[test]
+    fn capabilities_too_short() {
+        assert!(parse_capabilities(&[0x00, 0x3f, 0x07]).is_err());
+    }
+
+    #[test]
+    fn revision() {
+        let revision = parse_revision(b"NBRPVAAM 21 ENG \0\0");
+        assert_eq!(revision.raw, "NBRPVAAM 21 ENG");
+        assert_eq!(revision.software, "NBRPVAAM");
+        assert_eq!(revision.board.as_deref(), Some("21"));
+        assert_eq!(revision.other, ["ENG"]);
+    }
+}
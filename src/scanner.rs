@@ -0,0 +1,823 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serialport::SerialPort;
+use tokio::sync::mpsc;
+
+use crate::builder::ScannerBuilder;
+use crate::capture::Capture;
+use crate::command::{
+    ack_payload, nack_payload, wakeup, NackReason, WAKEUP_DELAY,
+};
+use crate::dispatch::Dispatcher;
+use crate::framer::Framer;
+use crate::image::VideoAssembler;
+use crate::param::{
+    change_all_code_types_payload, enable_params, param_request_payload,
+    param_send_payload, parse_param_send, ParamNumber,
+};
+use crate::protocol::{Action, ProtocolState, MAX_RETRIES};
+use crate::reply::{parse_revision, Revision};
+use crate::{
+    decode_with, wrap_into_with, ContentType, DecodeError, Direction, OpCode,
+    OwnedMessage, RawMessage, Source, SsiConfig, SsiError, Status,
+    TransmitFormat,
+};
+
+/// Default of `SsiConfig::read_timeout`
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_millis(10);
+/// How long the scanner gets to answer a command
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the rest of a frame may take to arrive after its last byte,
+/// before it is given up, see `Framer::expire`
+const FRAME_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Payload of a host frame without data
+pub(crate) fn bare_payload(opcode: OpCode) -> [u8; 3] {
+    [opcode.into(), Source::Host.into(), Status::default().into()]
+}
+
+/// `REQUEST_REVISION`, the cheapest request the scanner replies to
+pub(crate) fn revision_request() -> [u8; 3] {
+    bare_payload(OpCode::RequestRevision)
+}
+
+/// Trouble on the line, reported to the handler set with
+/// `Scanner::on_line_event`
+///
+/// `opcode` is that of the host frame concerned, `elapsed` counts from when
+/// it was first sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEvent {
+    /// The scanner NACKed the `attempt`th transmission of a host frame,
+    /// giving the `cause` if the NACK had one
+    Nack {
+        opcode: OpCode,
+        attempt: u8,
+        elapsed: Duration,
+        cause: Option<NackReason>,
+    },
+    /// A NACKed host frame was sent again as the `attempt`th transmission
+    Retransmit {
+        opcode: OpCode,
+        attempt: u8,
+        elapsed: Duration,
+    },
+    /// No (complete) reply to a host frame in time
+    Timeout { opcode: OpCode, elapsed: Duration },
+}
+
+type LineEventHandler = Box<dyn FnMut(&LineEvent) + Send>;
+
+/// Whether a read failed for the read timeout running out
+///
+/// Sockets report that as `WouldBlock` on some platforms.
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+/// Records `frame` if a capture is running
+///
+/// A capture that fails to write is stopped rather than failing the
+/// exchange with the scanner, it's only there for diagnosing.
+fn record(capture: &mut Option<Capture>, direction: Direction, frame: &[u8]) {
+    let Some(running) = capture else {
+        return;
+    };
+    if let Err(e) = running.record(direction, frame) {
+        eprintln!("Stopping capture: {e}");
+        *capture = None;
+    }
+}
+
+/// A connection to a scanner
+pub struct Scanner<T = Box<dyn SerialPort>> {
+    pub(crate) port: T,
+    pub(crate) config: SsiConfig,
+    /// Scratch buffer for ACKs
+    frame: Vec<u8>,
+    /// The last host frame, kept for resending it on a NACK
+    last_sent: Vec<u8>,
+    /// Bytes read but not yet handed out as a frame
+    framer: Framer,
+    /// Scans that arrived while waiting for an ACK, for `next_decode`
+    scans: VecDeque<OwnedMessage>,
+    /// When `last_sent` was first sent
+    sent_at: Instant,
+    /// When the last bytes arrived
+    read_at: Instant,
+    state: ProtocolState,
+    on_line_event: Option<LineEventHandler>,
+    capture: Option<Capture>,
+}
+
+impl Scanner {
+    /// Opens the serial port the scanner is connected to
+    pub fn open(
+        port_name: &str,
+        baud_rate: u32,
+        config: SsiConfig,
+    ) -> Result<Self, SsiError> {
+        let port = serialport::new(port_name, baud_rate)
+            .timeout(config.read_timeout)
+            .open()?;
+
+        Ok(Scanner::new(port, config))
+    }
+
+    /// Connects to a scanner behind a serial to Ethernet converter, which
+    /// passes the SSI frames through a raw TCP socket
+    pub fn connect_tcp(
+        addr: impl ToSocketAddrs,
+        config: SsiConfig,
+    ) -> Result<Scanner<TcpStream>, SsiError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(config.read_timeout))?;
+        // Frames are small and the handshake waits for every one of them
+        stream.set_nodelay(true)?;
+
+        Ok(Scanner::new(stream, config))
+    }
+
+    /// Starts setting up a connection with more options than `open`
+    pub fn builder(port_name: impl Into<String>) -> ScannerBuilder {
+        ScannerBuilder::new(port_name)
+    }
+}
+
+impl<T: Read + Write> Scanner<T> {
+    pub fn new(port: T, config: SsiConfig) -> Self {
+        Scanner {
+            port,
+            framer: Framer::new(config.clone()),
+            config,
+            frame: Vec::new(),
+            last_sent: Vec::new(),
+            scans: VecDeque::new(),
+            sent_at: Instant::now(),
+            read_at: Instant::now(),
+            state: ProtocolState::default(),
+            on_line_event: None,
+            capture: None,
+        }
+    }
+
+    /// The active configuration, clone it to set up further scanners the
+    /// same way
+    pub fn config(&self) -> &SsiConfig {
+        &self.config
+    }
+
+    /// The port, or whatever else the scanner is connected through
+    pub fn get_ref(&self) -> &T {
+        &self.port
+    }
+
+    /// The port, for settings like the baud rate that can change on a
+    /// live connection
+    ///
+    /// Reading from it directly bypasses the handshake.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.port
+    }
+
+    /// Closes the connection, returning the port
+    ///
+    /// Bytes read but not yet handed out as frames are lost.
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Records every frame sent and received from now on to `capture`
+    ///
+    /// The capture stops at the first write error, which is printed to
+    /// stderr, the connection carries on.
+    pub fn capture_to(&mut self, capture: Capture) {
+        self.capture = Some(capture);
+    }
+
+    /// Reports NACKs, retransmissions and timeouts to `handler` as they
+    /// happen, for a timeline of the line quality
+    pub fn on_line_event(
+        &mut self,
+        handler: impl FnMut(&LineEvent) + Send + 'static,
+    ) {
+        self.on_line_event = Some(Box::new(handler));
+    }
+
+    fn emit(&mut self, event: LineEvent) {
+        if let Some(handler) = &mut self.on_line_event {
+            handler(&event);
+        }
+    }
+
+    /// Opcode of the last host frame
+    fn sent_opcode(&self) -> OpCode {
+        self.last_sent.get(1).map_or(OpCode::Other(0), OpCode::from)
+    }
+
+    fn emit_timeout(&mut self) {
+        self.emit(LineEvent::Timeout {
+            opcode: self.sent_opcode(),
+            elapsed: self.sent_at.elapsed(),
+        });
+    }
+
+    /// Sends a host frame carrying `payload` (opcode, source, status, data)
+    ///
+    /// The scanner's ACK or NACK is handled along with the frames read
+    /// afterwards.
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), SsiError> {
+        if !self.config.command_delay.is_zero() {
+            thread::sleep(self.config.command_delay);
+        }
+
+        wrap_into_with(&mut self.last_sent, payload, self.config.checksum)?;
+        self.port.write_all(&self.last_sent)?;
+        record(&mut self.capture, Direction::HostToScanner, &self.last_sent);
+        self.sent_at = Instant::now();
+        self.state.sent(self.sent_opcode());
+
+        Ok(())
+    }
+
+    /// Answers a received frame as the protocol requires
+    ///
+    /// Does nothing without `SsiConfig::auto_ack`.
+    pub(crate) fn handshake(
+        &mut self,
+        opcode: &OpCode,
+        status: &Status,
+        data: &[u8],
+    ) -> Result<(), SsiError> {
+        if !self.config.auto_ack {
+            return Ok(());
+        }
+
+        let cause = data.first().map(|&cause| NackReason::from(cause));
+        match self.state.received(opcode) {
+            Action::None => (),
+            Action::Ack => {
+                // Echoing the continuation bit tells which packet is
+                // acknowledged
+                wrap_into_with(
+                    &mut self.frame,
+                    &ack_payload(
+                        self.config.ack_source,
+                        *status & Status::Continuation,
+                    ),
+                    self.config.checksum,
+                )?;
+                self.port.write_all(&self.frame)?;
+                record(
+                    &mut self.capture,
+                    Direction::HostToScanner,
+                    &self.frame,
+                );
+            }
+            Action::Resend => {
+                let retries = match self.state {
+                    ProtocolState::AwaitingAck { retries, .. } => retries,
+                    _ => 0,
+                };
+                self.emit(LineEvent::Nack {
+                    opcode: self.sent_opcode(),
+                    attempt: retries,
+                    elapsed: self.sent_at.elapsed(),
+                    cause,
+                });
+                self.retransmit(retries + 1)?;
+            }
+            Action::GiveUp => {
+                self.emit(LineEvent::Nack {
+                    opcode: self.sent_opcode(),
+                    attempt: MAX_RETRIES + 1,
+                    elapsed: self.sent_at.elapsed(),
+                    cause,
+                });
+                return Err(SsiError::RetransmitExhausted);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the last host frame again as the `attempt`th transmission,
+    /// with `Status::Retransmit` set
+    fn retransmit(&mut self, attempt: u8) -> Result<(), SsiError> {
+        let end = self.last_sent.len() - 2;
+        let mut payload = self.last_sent[1..end].to_vec();
+        payload[2] |= u8::from(Status::Retransmit);
+        wrap_into_with(&mut self.last_sent, &payload, self.config.checksum)?;
+
+        self.port.write_all(&self.last_sent)?;
+        record(&mut self.capture, Direction::HostToScanner, &self.last_sent);
+        self.emit(LineEvent::Retransmit {
+            opcode: self.sent_opcode(),
+            attempt,
+            elapsed: self.sent_at.elapsed(),
+        });
+
+        Ok(())
+    }
+
+    /// Asks the scanner to send a frame again that arrived corrupted
+    ///
+    /// Does nothing without `SsiConfig::auto_ack`.
+    fn reject(&mut self) -> Result<(), SsiError> {
+        if !self.config.auto_ack {
+            return Ok(());
+        }
+
+        wrap_into_with(
+            &mut self.frame,
+            &nack_payload(self.config.ack_source, NackReason::Resend),
+            self.config.checksum,
+        )?;
+        self.port.write_all(&self.frame)?;
+        record(&mut self.capture, Direction::HostToScanner, &self.frame);
+
+        Ok(())
+    }
+
+    /// Copies the next complete frame into `buf` and returns its length
+    ///
+    /// Scanners may send frames back to back without waiting for the ACK
+    /// of the previous one, so a single read can return several frames or
+    /// end halfway through one. Whatever follows the frame is kept for the
+    /// next call. Returns `None` if no complete frame arrived within the
+    /// port's read timeout, and the decode error of frames the `Framer`
+    /// rejects. Frames failing their checksum are NACKed, a frame that
+    /// stops arriving halfway is dropped after `FRAME_TIMEOUT`.
+    pub(crate) fn read_frame(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, SsiError> {
+        let frame = match self.framer.next_frame() {
+            Some(frame) => frame,
+            None => {
+                let mut chunk = [0; 256];
+                match self.port.read(&mut chunk) {
+                    Ok(t) => {
+                        self.framer.push(&chunk[..t]);
+                        self.read_at = Instant::now();
+                    }
+                    Err(ref e) if is_timeout(e) => {
+                        if self.read_at.elapsed() < FRAME_TIMEOUT {
+                            return Ok(None);
+                        }
+                        return match self.framer.expire() {
+                            Some(e) => Err(e.into()),
+                            None => Ok(None),
+                        };
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+                let Some(frame) = self.framer.next_frame() else {
+                    return Ok(None);
+                };
+                frame
+            }
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                if let DecodeError::InvalidChecksum { frame, .. } = &e {
+                    record(&mut self.capture, Direction::ScannerToHost, frame);
+                    self.reject()?;
+                }
+                return Err(e.into());
+            }
+        };
+
+        // A short buffer truncates the frame, which then fails to decode
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        record(&mut self.capture, Direction::ScannerToHost, &frame);
+
+        Ok(Some(len))
+    }
+
+    /// Reads and acknowledges the next frame
+    ///
+    /// Returns `None` if nothing arrived within the port's read timeout.
+    /// The handshake is taken care of, ACKs and NACKs for host frames are
+    /// returned like any other message.
+    pub fn receive<'b>(
+        &mut self,
+        buf: &'b mut [u8],
+    ) -> Result<Option<RawMessage<'b>>, SsiError> {
+        let Some(t) = self.read_frame(buf)? else {
+            return Ok(None);
+        };
+
+        let message = decode_with(&buf[..t], &self.config)?;
+        self.handshake(&message.opcode, &message.status, message.data)?;
+
+        Ok(Some(message))
+    }
+
+    /// Waits up to `timeout` for the next scan
+    ///
+    /// Returns `None` if nothing was scanned in time. The scan is
+    /// acknowledged before it is returned, other frames are acknowledged
+    /// and dropped, as are frames that fail to decode.
+    pub fn next_decode(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<OwnedMessage>, SsiError> {
+        if let Some(scan) = self.scans.pop_front() {
+            return Ok(Some(scan));
+        }
+
+        let start = Instant::now();
+        let mut buf = [0; 1000];
+
+        while start.elapsed() < timeout {
+            match self.receive(&mut buf) {
+                Ok(Some(message)) if message.opcode == OpCode::DecodeData => {
+                    return Ok(Some(message.to_owned()));
+                }
+                Ok(_) | Err(SsiError::Decode(_)) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Moves reading to a thread of its own, which sends every frame
+    /// received to the returned channel
+    ///
+    /// Frames are acknowledged as they arrive, before they are taken from
+    /// the channel. Once `capacity` of them are waiting, reading pauses until
+    /// the consumer catches up. Decode errors are sent and reading goes on,
+    /// the thread ends with the first other error, once the receiver is
+    /// dropped or on `SsiConfig::shutdown`.
+    pub fn into_stream(
+        mut self,
+        capacity: usize,
+    ) -> mpsc::Receiver<Result<OwnedMessage, SsiError>>
+    where
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(capacity);
+
+        thread::spawn(move || {
+            let mut buf = [0; 1000];
+            for scan in std::mem::take(&mut self.scans) {
+                if sender.blocking_send(Ok(scan)).is_err() {
+                    return;
+                }
+            }
+
+            while !self.is_shut_down() {
+                let (result, done) = match self.receive(&mut buf) {
+                    Ok(Some(message)) => (Ok(message.to_owned()), false),
+                    Ok(None) => continue,
+                    Err(e @ SsiError::Decode(_)) => (Err(e), false),
+                    Err(e) => (Err(e), true),
+                };
+                if sender.blocking_send(result).is_err() || done {
+                    return;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    /// Reads video frames as the scanner sends them in video mode, see
+    /// `VideoAssembler`
+    ///
+    /// Other frames are acknowledged and dropped. Decode errors are passed
+    /// on and reading goes on, iteration ends after any other error.
+    pub fn video_frames(
+        &mut self,
+    ) -> impl Iterator<Item = Result<Vec<u8>, SsiError>> + '_ {
+        let mut assembler = VideoAssembler::new();
+        let mut buf = [0; 1000];
+        let mut done = false;
+
+        std::iter::from_fn(move || loop {
+            if done {
+                return None;
+            }
+            match self.receive(&mut buf) {
+                Ok(Some(message)) => {
+                    if let Some(frame) = assembler.push(&message) {
+                        return Some(Ok(frame));
+                    }
+                }
+                Ok(None) => (),
+                Err(e @ SsiError::Decode(_)) => return Some(Err(e)),
+                Err(e) => {
+                    done = true;
+                    return Some(Err(e));
+                }
+            }
+        })
+    }
+
+    /// Reads frames until `SsiConfig::shutdown`, handing each one to
+    /// `dispatcher`
+    ///
+    /// Only fails on I/O errors, decode errors go to the dispatcher.
+    pub fn dispatch(
+        &mut self,
+        dispatcher: &mut Dispatcher<'_>,
+    ) -> Result<(), SsiError> {
+        let mut buf = [0; 1000];
+
+        while !self.is_shut_down() {
+            match self.receive(&mut buf) {
+                Ok(Some(message)) => dispatcher.dispatch(&message),
+                Ok(None) => (),
+                Err(SsiError::Decode(e)) => dispatcher.error(&e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `SsiConfig::shutdown` asks the receive loops to stop
+    pub(crate) fn is_shut_down(&self) -> bool {
+        self.config
+            .shutdown
+            .as_ref()
+            .is_some_and(|shutdown| *shutdown.borrow())
+    }
+
+    /// Sends `request` and collects the replies until `is_final` returns
+    /// true for one of them
+    ///
+    /// Each reply is acknowledged, and `request` is resent if the scanner
+    /// NACKs it. Frames that fail to decode are skipped. Fails with
+    /// `SsiError::Timeout` if the sequence isn't complete within `timeout`.
+    /// Meant for commands answered by several frames, like parameter dumps.
+    pub fn exchange(
+        &mut self,
+        request: &[u8],
+        timeout: Duration,
+        mut is_final: impl FnMut(&RawMessage) -> bool,
+    ) -> Result<Vec<OwnedMessage>, SsiError> {
+        let start = Instant::now();
+        let mut replies = Vec::new();
+        let mut buf = [0; 1000];
+
+        self.send(request)?;
+        while start.elapsed() < timeout {
+            let message = match self.receive(&mut buf) {
+                Ok(Some(message)) => message,
+                Ok(None) | Err(SsiError::Decode(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            if let OpCode::Ack | OpCode::Nack = message.opcode {
+                continue;
+            }
+
+            let done = is_final(&message);
+            replies.push(message.to_owned());
+            if done {
+                return Ok(replies);
+            }
+        }
+
+        self.emit_timeout();
+        Err(SsiError::Timeout)
+    }
+
+    /// Reads and discards everything the host has buffered
+    ///
+    /// Reads until the port has nothing more to offer or `timeout` has
+    /// passed, and returns the discarded bytes for inspection. Use before a
+    /// request/reply exchange to make sure the next frame read is the
+    /// actual reply. Unlike `FLUSH_QUEUE`, this doesn't affect data still
+    /// queued on the scanner.
+    pub fn drain(&mut self, timeout: Duration) -> Result<Vec<u8>, SsiError> {
+        let start = Instant::now();
+        let mut discarded = self.framer.take_buffered();
+        let mut buf = [0; 256];
+
+        while start.elapsed() < timeout {
+            match self.port.read(&mut buf) {
+                Ok(0) => break,
+                Ok(t) => discarded.extend_from_slice(&buf[..t]),
+                Err(ref e) if is_timeout(e) => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(discarded)
+    }
+
+    /// Sends a command and waits up to `timeout` for the scanner to ACK it
+    ///
+    /// Don't send the next command before this returns, the scanner only
+    /// handles one at a time. Scans arriving in the meantime are
+    /// acknowledged and kept for `next_decode`, other frames are
+    /// acknowledged and dropped, as are frames that fail to decode. A NACKed
+    /// command is resent as usual, and so is one without an ACK within
+    /// `timeout`, waiting `timeout` again each time. Fails with
+    /// `SsiError::Timeout` once `MAX_RETRIES` resends went unanswered.
+    pub fn send_and_await_ack(
+        &mut self,
+        payload: &[u8],
+        timeout: Duration,
+    ) -> Result<(), SsiError> {
+        let mut buf = [0; 1000];
+
+        self.send(payload)?;
+        loop {
+            let start = Instant::now();
+            while start.elapsed() < timeout {
+                match self.receive(&mut buf) {
+                    Ok(Some(RawMessage {
+                        opcode: OpCode::Ack,
+                        source: Source::Scanner,
+                        ..
+                    })) => return Ok(()),
+                    Ok(Some(message))
+                        if message.opcode == OpCode::DecodeData =>
+                    {
+                        self.scans.push_back(message.to_owned());
+                    }
+                    Ok(_) | Err(SsiError::Decode(_)) => (),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            self.emit_timeout();
+            match (self.state.retry(), self.state) {
+                (
+                    Action::Resend,
+                    ProtocolState::AwaitingAck { retries, .. },
+                ) => self.retransmit(retries + 1)?,
+                _ => return Err(SsiError::Timeout),
+            }
+        }
+    }
+
+    /// `send_and_await_ack` with the default timeout
+    fn command(&mut self, payload: &[u8]) -> Result<(), SsiError> {
+        self.send_and_await_ack(payload, COMMAND_TIMEOUT)
+    }
+
+    /// Scans as if the trigger was pulled, see `start_session`
+    pub fn start_session(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::StartSession))
+    }
+
+    pub fn stop_session(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::StopSession))
+    }
+
+    /// Discards the frames the scanner queued for sending, see
+    /// `flush_queue`
+    pub fn flush_queue(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::FlushQueue))
+    }
+
+    /// Keeps the illumination LEDs on, see `illumination_on`
+    pub fn illumination_on(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::IlluminationOn))
+    }
+
+    pub fn illumination_off(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::IlluminationOff))
+    }
+
+    /// Restores the factory defaults of all parameters
+    pub fn param_defaults(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::ParamDefaults))
+    }
+
+    /// Puts the scanner into low power mode, see `sleep`
+    pub fn sleep(&mut self) -> Result<(), SsiError> {
+        self.command(&bare_payload(OpCode::Sleep))
+    }
+
+    /// Wakes the scanner and waits until it is ready for frames, see
+    /// `wakeup`
+    pub fn wakeup(&mut self) -> Result<(), SsiError> {
+        self.port.write_all(&wakeup())?;
+        record(&mut self.capture, Direction::HostToScanner, &wakeup());
+        thread::sleep(WAKEUP_DELAY);
+
+        Ok(())
+    }
+
+    /// Writes parameter values, surviving a power cycle if `permanent`
+    pub fn set_params(
+        &mut self,
+        params: &[(ParamNumber, u8)],
+        permanent: bool,
+    ) -> Result<(), SsiError> {
+        self.command(&param_send_payload(params, permanent))
+    }
+
+    /// Disables every symbology but `types`, surviving a power cycle if
+    /// `permanent`
+    ///
+    /// Fails before sending anything if a symbology has no known
+    /// parameter, see `ParamNumber::enabling`.
+    pub fn enable_only(
+        &mut self,
+        types: &[ContentType],
+        permanent: bool,
+    ) -> Result<(), SsiError> {
+        let params = enable_params(types)?;
+        self.command(&change_all_code_types_payload(false, permanent))?;
+        self.set_params(&params, permanent)
+    }
+
+    /// Reads parameter values
+    ///
+    /// Parameters the scanner doesn't know are missing from the result.
+    pub fn get_params(
+        &mut self,
+        params: &[ParamNumber],
+    ) -> Result<Vec<(ParamNumber, u8)>, SsiError> {
+        let request = param_request_payload(params);
+        let replies = self.exchange(&request, COMMAND_TIMEOUT, |message| {
+            message.opcode == OpCode::ParamSend
+                && !message.status.contains(Status::Continuation)
+        })?;
+
+        let mut values = Vec::new();
+        // Frames arriving in between are no replies
+        for reply in replies.iter().filter(|r| r.opcode == OpCode::ParamSend) {
+            values.extend(parse_param_send(&reply.data)?);
+        }
+
+        Ok(values)
+    }
+
+    /// Sets prefix, suffixes and code ID transmission and verifies the
+    /// scanner took them
+    ///
+    /// The change is temporary. Fails with `SsiError::ParamMismatch` if a
+    /// parameter reads back differently.
+    pub fn configure_transmit(
+        &mut self,
+        format: &TransmitFormat,
+    ) -> Result<(), SsiError> {
+        let params = format.params();
+        self.set_params(&params, false)?;
+
+        let numbers: Vec<ParamNumber> =
+            params.iter().map(|(param, _)| *param).collect();
+        let read = self.get_params(&numbers)?;
+        for (param, written) in params {
+            let read = read
+                .iter()
+                .find(|(number, _)| *number == param)
+                .map(|(_, value)| *value);
+            if read != Some(written) {
+                return Err(SsiError::ParamMismatch {
+                    param,
+                    written,
+                    read,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the scanner is still responsive
+    ///
+    /// Asks for the scanner's revision and fails with `SsiError::Timeout`
+    /// if there is no answer within `timeout`. Frames arriving in the
+    /// meantime are acknowledged and dropped.
+    pub fn ping(&mut self, timeout: Duration) -> Result<(), SsiError> {
+        self.exchange(&revision_request(), timeout, |message| {
+            message.opcode == OpCode::ReplyRevision
+        })?;
+
+        Ok(())
+    }
+
+    /// Reads the scanner's software revision
+    pub fn revision(&mut self) -> Result<Revision, SsiError> {
+        let replies =
+            self.exchange(&revision_request(), COMMAND_TIMEOUT, |message| {
+                message.opcode == OpCode::ReplyRevision
+            })?;
+        // exchange only returns once the reply is there
+        let reply = replies.last().unwrap();
+
+        Ok(parse_revision(&reply.data))
+    }
+}
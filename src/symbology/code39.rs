@@ -0,0 +1,104 @@
+//! Code 39 variants: Code 32 (Italian Pharmacode) and Trioptic
+//!
+//! Both are printed as Code 39 symbols. Code 32 packs a 9 digit AIC number
+//! (8 digits and a check digit) of Italian pharmaceuticals into base 32,
+//! the scanner transmits the decimal number. Trioptic marks computer tape
+//! cartridges with 6 Code 39 characters.
+
+use super::{check_digits, split_check, ValidationError};
+
+/// The Code 39 character set, without the `*` start/stop character
+fn is_code39_char(byte: u8) -> bool {
+    byte.is_ascii_digit()
+        || byte.is_ascii_uppercase()
+        || matches!(byte, b'-' | b'.' | b' ' | b'$' | b'/' | b'+' | b'%')
+}
+
+/// Code 32 check digit: digits in odd positions count as they are, those
+/// in even positions doubled with the digits of the product summed
+///
+/// `digits` must only contain ASCII digits.
+pub fn code32_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, digit)| {
+            let value = u32::from(digit - b'0');
+            if i % 2 == 1 {
+                value * 2 / 10 + value * 2 % 10
+            } else {
+                value
+            }
+        })
+        .sum();
+
+    b'0' + (sum % 10) as u8
+}
+
+/// Validates `Code32` content and returns the 9 digit AIC number
+///
+/// The `A` the scanner prepends with "Code 32 Prefix" enabled is
+/// stripped. The check digit is verified and kept, as it is part of the
+/// printed number.
+pub fn parse_code32(content: &[u8]) -> Result<&str, ValidationError> {
+    let digits = content.strip_prefix(b"A").unwrap_or(content);
+    if digits.len() != 9 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    check_digits(digits)?;
+    split_check(digits, code32_check_digit)?;
+
+    // Only ASCII digits at this point
+    Ok(std::str::from_utf8(digits).unwrap())
+}
+
+/// Validates `Trioptic` content of 6 Code 39 characters
+pub fn validate_trioptic(content: &[u8]) -> Result<&str, ValidationError> {
+    if content.len() != 6 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    if let Some(position) = content.iter().position(|b| !is_code39_char(*b)) {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+
+    // Only ASCII at this point
+    Ok(std::str::from_utf8(content).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code32() {
+        assert_eq!(code32_check_digit(b"12345678"), b'8');
+        assert_eq!(parse_code32(b"123456788"), Ok("123456788"));
+        assert_eq!(parse_code32(b"A123456788"), Ok("123456788"));
+        assert_eq!(
+            parse_code32(b"123456780"),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'8',
+                found: b'0'
+            })
+        );
+        assert_eq!(
+            parse_code32(b"12345678"),
+            Err(ValidationError::InvalidLength(8))
+        );
+    }
+
+    #[test]
+    fn trioptic() {
+        assert_eq!(validate_trioptic(b"AB12$/"), Ok("AB12$/"));
+        assert_eq!(
+            validate_trioptic(b"AB12a/"),
+            Err(ValidationError::InvalidCharacter {
+                position: 4,
+                byte: b'a'
+            })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! North American coupon codes
+//!
+//! `Coupon` delivers the UPC-A (number system 5) part of a coupon, followed
+//! by the GS1-128 extended coupon code if the label carries both. The
+//! GS1 DataBar Expanded coupon (`RssExpandedCoupon`) only carries the
+//! extended code. The extended code is the element string of AI (8110),
+//! whose fields are described in the GS1 US coupon application guideline.
+
+use std::ops::RangeInclusive;
+
+use super::{check_digits, ValidationError};
+
+const EXTENDED_AI: &[u8] = b"8110";
+const GROUP_SEPARATOR: u8 = 0x1d;
+const UPC_LEN: usize = 12;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Coupon<'a> {
+    /// The complete content, for anything not covered below
+    pub raw: &'a [u8],
+    /// UPC-A coupon (number system 5), including its check digit
+    pub upc: Option<&'a str>,
+    /// AI (8110) extended coupon code
+    pub extended: Option<ExtendedCoupon<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExtendedCoupon<'a> {
+    pub company_prefix: &'a str,
+    pub offer_code: &'a str,
+    pub save_value: &'a str,
+    pub purchase_requirement: &'a str,
+    pub purchase_requirement_code: u8,
+    pub family_code: &'a str,
+    /// Optional data fields following the mandatory ones, unparsed
+    pub optional_fields: &'a str,
+}
+
+/// Parses the content of a `Coupon` or `RssExpandedCoupon` message
+pub fn parse_coupon(content: &[u8]) -> Result<Coupon<'_>, ValidationError> {
+    let (upc, extended) = match content {
+        _ if content.starts_with(EXTENDED_AI) => (None, Some(content)),
+        _ if content.len() == UPC_LEN => (Some(content), None),
+        _ if content.len() > UPC_LEN
+            && content[UPC_LEN..].starts_with(EXTENDED_AI) =>
+        {
+            let (upc, extended) = content.split_at(UPC_LEN);
+            (Some(upc), Some(extended))
+        }
+        _ => return Err(ValidationError::InvalidLength(content.len())),
+    };
+
+    let upc = upc
+        .map(|upc| {
+            check_digits(upc)?;
+            // Only ASCII digits at this point
+            Ok(std::str::from_utf8(upc).unwrap())
+        })
+        .transpose()?;
+
+    // Other element strings may follow after a group separator
+    let extended = extended
+        .map(|extended| {
+            let end = extended
+                .iter()
+                .position(|b| *b == GROUP_SEPARATOR)
+                .unwrap_or(extended.len());
+            parse_extended(&extended[EXTENDED_AI.len()..end])
+        })
+        .transpose()?;
+
+    Ok(Coupon {
+        raw: content,
+        upc,
+        extended,
+    })
+}
+
+fn parse_extended(data: &[u8]) -> Result<ExtendedCoupon<'_>, ValidationError> {
+    check_digits(data)?;
+    // Only ASCII digits at this point
+    let mut fields = Fields {
+        data: std::str::from_utf8(data).unwrap(),
+        offset: 0,
+    };
+
+    let company_prefix = fields.take_vli(6..=12)?;
+    let offer_code = fields.take(6)?;
+    let save_value = fields.take_vli(1..=5)?;
+    let purchase_requirement = fields.take_vli(1..=5)?;
+    let purchase_requirement_code = fields.take(1)?.as_bytes()[0] - b'0';
+    let family_code = fields.take(3)?;
+
+    Ok(ExtendedCoupon {
+        company_prefix,
+        offer_code,
+        save_value,
+        purchase_requirement,
+        purchase_requirement_code,
+        family_code,
+        optional_fields: &fields.data[fields.offset..],
+    })
+}
+
+struct Fields<'a> {
+    data: &'a str,
+    offset: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a str, ValidationError> {
+        let field = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or(ValidationError::InvalidLength(self.data.len()))?;
+        self.offset += len;
+        Ok(field)
+    }
+
+    /// Takes a field preceded by its variable length indicator
+    ///
+    /// The indicator digit is the offset into the range of valid lengths.
+    fn take_vli(
+        &mut self,
+        lengths: RangeInclusive<usize>,
+    ) -> Result<&'a str, ValidationError> {
+        let position = self.offset;
+        let vli = self.take(1)?.as_bytes()[0];
+        let len = lengths.start() + usize::from(vli - b'0');
+        if !lengths.contains(&len) {
+            return Err(ValidationError::InvalidCharacter {
+                position,
+                byte: vli,
+            });
+        }
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Prefix 012345 and offer 678901 (VLI 0), save value 50 (VLI 1),
+    // purchase requirement 1 (VLI 0) of code 0, family 123
+    const EXTENDED: &[u8] = b"81100012345678901150010123";
+
+    #[test]
+    fn upc_and_extended() {
+        let content = [&b"512345678900"[..], EXTENDED, b"\x1d8200x"].concat();
+        let coupon = parse_coupon(&content).unwrap();
+        assert_eq!(coupon.upc, Some("512345678900"));
+        assert_eq!(
+            coupon.extended,
+            Some(ExtendedCoupon {
+                company_prefix: "012345",
+                offer_code: "678901",
+                save_value: "50",
+                purchase_requirement: "1",
+                purchase_requirement_code: 0,
+                family_code: "123",
+                optional_fields: "",
+            })
+        );
+
+        let coupon = parse_coupon(EXTENDED).unwrap();
+        assert_eq!(coupon.upc, None);
+        assert_eq!(coupon.extended.unwrap().save_value, "50");
+    }
+
+    #[test]
+    fn vli_out_of_range() {
+        // A company prefix of 6 + 9 digits
+        assert_eq!(
+            parse_coupon(b"81109012345678901150010123"),
+            Err(ValidationError::InvalidCharacter {
+                position: 0,
+                byte: b'9'
+            })
+        );
+        assert_eq!(
+            parse_coupon(b"5123456789"),
+            Err(ValidationError::InvalidLength(10))
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Extended Channel Interpretation (ECI) for the 2D symbologies
+//!
+//! With the scanner's ECI protocol enabled, Aztec, Data Matrix, QR, Han Xin,
+//! Dotcode, Grid Matrix and Maxicode content is transmitted according to
+//! the AIM ECI transmission protocol: a backslash followed by six digits
+//! selects the character set for the bytes that follow, and a literal
+//! backslash in the data is doubled.
+
+use std::borrow::Cow;
+
+use super::ValidationError;
+
+const ESCAPE: u8 = b'\\';
+const DESIGNATOR_LEN: usize = 6;
+
+/// Character set selected by an ECI designator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Cp437,
+    /// ISO-8859-1 to ISO-8859-16, by part number
+    Iso8859(u8),
+    ShiftJis,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1256,
+    Utf16Be,
+    Utf8,
+    Ascii,
+    Big5,
+    Gb2312,
+    EucKr,
+    Gb18030,
+    Other(u32),
+}
+
+impl From<u32> for Charset {
+    fn from(eci: u32) -> Self {
+        match eci {
+            0 | 2 => Charset::Cp437,
+            1 | 3 => Charset::Iso8859(1),
+            4..=13 => Charset::Iso8859((eci - 2) as u8),
+            15..=18 => Charset::Iso8859((eci - 2) as u8),
+            20 => Charset::ShiftJis,
+            21 => Charset::Windows1250,
+            22 => Charset::Windows1251,
+            23 => Charset::Windows1252,
+            24 => Charset::Windows1256,
+            25 => Charset::Utf16Be,
+            26 => Charset::Utf8,
+            27 | 170 => Charset::Ascii,
+            28 => Charset::Big5,
+            29 => Charset::Gb2312,
+            30 => Charset::EucKr,
+            32 => Charset::Gb18030,
+            _ => Charset::Other(eci),
+        }
+    }
+}
+
+impl Charset {
+    /// Converts `data` to a string, if the character set is supported
+    ///
+    /// Only the character sets that don't need conversion tables are
+    /// supported: ASCII, ISO-8859-1, UTF-8 and UTF-16BE. Everything else
+    /// returns `None`, leaving the conversion to the caller.
+    pub fn decode(self, data: &[u8]) -> Option<String> {
+        match self {
+            Charset::Ascii if data.is_ascii() => {
+                Some(data.iter().copied().map(char::from).collect())
+            }
+            Charset::Iso8859(1) => {
+                Some(data.iter().copied().map(char::from).collect())
+            }
+            Charset::Utf8 => String::from_utf8(data.to_vec()).ok(),
+            Charset::Utf16Be if data.len() % 2 == 0 => {
+                let units = data
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+                char::decode_utf16(units).collect::<Result<_, _>>().ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A run of content in a single character set
+#[derive(Debug, PartialEq, Eq)]
+pub struct EciSegment<'a> {
+    /// The ECI designator selecting the character set, `None` before the
+    /// first designator
+    pub eci: Option<u32>,
+    /// The content with escaped backslashes resolved
+    pub data: Cow<'a, [u8]>,
+}
+
+impl EciSegment<'_> {
+    pub fn charset(&self) -> Option<Charset> {
+        self.eci.map(Charset::from)
+    }
+}
+
+/// Splits ECI protocol content into segments per character set
+pub fn split_eci(
+    content: &[u8],
+) -> Result<Vec<EciSegment<'_>>, ValidationError> {
+    let mut segments = Vec::new();
+    let mut eci = None;
+    let mut start = 0;
+    let mut position = 0;
+    // Only allocated once an escaped backslash shows up
+    let mut unescaped: Option<Vec<u8>> = None;
+
+    while position < content.len() {
+        if content[position] != ESCAPE {
+            position += 1;
+            continue;
+        }
+
+        if content.get(position + 1) == Some(&ESCAPE) {
+            unescaped
+                .get_or_insert_with(Vec::new)
+                .extend_from_slice(&content[start..=position]);
+            position += 2;
+            start = position;
+            continue;
+        }
+
+        let designator = content
+            .get(position + 1..position + 1 + DESIGNATOR_LEN)
+            .filter(|designator| designator.iter().all(u8::is_ascii_digit))
+            .ok_or(ValidationError::InvalidCharacter {
+                position,
+                byte: ESCAPE,
+            })?;
+
+        push_segment(
+            &mut segments,
+            eci,
+            &content[start..position],
+            unescaped.take(),
+        );
+        eci = Some(
+            designator
+                .iter()
+                .fold(0, |acc, digit| acc * 10 + u32::from(digit - b'0')),
+        );
+        position += 1 + DESIGNATOR_LEN;
+        start = position;
+    }
+    push_segment(&mut segments, eci, &content[start..], unescaped);
+
+    Ok(segments)
+}
+
+fn push_segment<'a>(
+    segments: &mut Vec<EciSegment<'a>>,
+    eci: Option<u32>,
+    data: &'a [u8],
+    unescaped: Option<Vec<u8>>,
+) {
+    let data = match unescaped {
+        Some(mut unescaped) => {
+            unescaped.extend_from_slice(data);
+            Cow::Owned(unescaped)
+        }
+        None => Cow::Borrowed(data),
+    };
+    if !data.is_empty() {
+        segments.push(EciSegment { eci, data });
+    }
+}
+
+/// Decodes ECI protocol content into a string
+///
+/// Content before the first designator is interpreted as `default`, which
+/// depends on the symbology (e.g. ISO-8859-1 for QR, GB18030 for Han Xin).
+/// Returns `None` if any segment uses a character set `Charset::decode`
+/// doesn't support.
+pub fn decode_eci(
+    content: &[u8],
+    default: Charset,
+) -> Result<Option<String>, ValidationError> {
+    let segments = split_eci(content)?;
+
+    Ok(segments
+        .iter()
+        .map(|segment| {
+            segment.charset().unwrap_or(default).decode(&segment.data)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments() {
+        let segments = split_eci(b"ab\\000026\xc3\xa4\\\\x").unwrap();
+        assert_eq!(
+            segments,
+            [
+                EciSegment {
+                    eci: None,
+                    data: Cow::Borrowed(b"ab"),
+                },
+                EciSegment {
+                    eci: Some(26),
+                    data: Cow::Owned(b"\xc3\xa4\\x".to_vec()),
+                },
+            ]
+        );
+        assert_eq!(segments[1].charset(), Some(Charset::Utf8));
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(
+            decode_eci(b"\xe4\\000026\xc3\xa4", Charset::Iso8859(1)),
+            Ok(Some("ää".to_string()))
+        );
+        // Shift JIS needs tables
+        assert_eq!(decode_eci(b"\\000020x", Charset::Utf8), Ok(None));
+    }
+
+    #[test]
+    fn bad_designator() {
+        assert_eq!(
+            split_eci(b"a\\12x"),
+            Err(ValidationError::InvalidCharacter {
+                position: 1,
+                byte: b'\\'
+            })
+        );
+    }
+}
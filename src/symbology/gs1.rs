@@ -0,0 +1,163 @@
+//! GS1 element strings, as carried by GS1-128, GS1 DataBar, GS1 Data Matrix
+//! and GS1 QR
+//!
+//! The content is a sequence of elements, each an application identifier
+//! (AI) of two to four digits followed by its value. AIs with a predefined
+//! length, like (01) GTIN or (17) expiry date, simply run into the next AI.
+//! All other values end at a group separator (0x1d, FNC1 in the symbol) or
+//! at the end of the content.
+
+use super::ValidationError;
+
+const GROUP_SEPARATOR: u8 = 0x1d;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gs1Element<'a> {
+    /// The application identifier, e.g. "01" for the GTIN or "10" for the
+    /// batch/lot number
+    pub ai: &'a str,
+    pub value: &'a str,
+}
+
+/// Length of the AI starting with the two digits `prefix`, `None` for AIs
+/// not assigned by GS1
+fn ai_len(prefix: u8) -> Option<usize> {
+    let len = match prefix {
+        0..=22 | 30 | 37 | 90..=99 => 2,
+        23..=25 | 40..=42 | 71 => 3,
+        31..=36 | 39 | 43 | 70 | 72 | 80..=82 => 4,
+        _ => return None,
+    };
+
+    Some(len)
+}
+
+/// Length of the value of the AI starting with `prefix`, if predefined
+fn predefined_len(prefix: u8) -> Option<usize> {
+    let len = match prefix {
+        0 => 18,
+        1..=3 => 14,
+        4 => 16,
+        11..=19 | 31..=36 => 6,
+        20 => 2,
+        41 => 13,
+        _ => return None,
+    };
+
+    Some(len)
+}
+
+/// Splits GS1 element strings into AI/value pairs
+///
+/// A group separator at the start, which some scanners transmit for the
+/// leading FNC1, is skipped. Fails on AIs not assigned by GS1, as there is
+/// no telling where their value ends.
+pub fn parse_gs1(
+    content: &[u8],
+) -> Result<Vec<Gs1Element<'_>>, ValidationError> {
+    let start = usize::from(content.first() == Some(&GROUP_SEPARATOR));
+    if content.len() <= start {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    if let Some(position) = content.iter().position(|b| !b.is_ascii()) {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+    // Only ASCII at this point
+    let text = std::str::from_utf8(content).unwrap();
+
+    let mut elements = Vec::new();
+    let mut offset = start;
+    while offset < content.len() {
+        let rest = &content[offset..];
+        let invalid = |i: usize| ValidationError::InvalidCharacter {
+            position: offset + i,
+            byte: rest[i],
+        };
+        let truncated = ValidationError::InvalidLength(content.len());
+        let &[tens, ones, ..] = rest else {
+            return Err(truncated);
+        };
+        if let Some(i) = [tens, ones].iter().position(|b| !b.is_ascii_digit()) {
+            return Err(invalid(i));
+        }
+        let prefix = (tens - b'0') * 10 + (ones - b'0');
+        let ai_len = ai_len(prefix).ok_or_else(|| invalid(0))?;
+        let ai = rest.get(..ai_len).ok_or(truncated.clone())?;
+        if let Some(i) = ai.iter().position(|b| !b.is_ascii_digit()) {
+            return Err(invalid(i));
+        }
+
+        let value_len = match predefined_len(prefix) {
+            Some(len) => {
+                let value = rest.get(ai_len..ai_len + len).ok_or(truncated)?;
+                if let Some(i) = value.iter().position(|b| !b.is_ascii_digit())
+                {
+                    return Err(invalid(ai_len + i));
+                }
+                len
+            }
+            None => rest[ai_len..]
+                .iter()
+                .position(|b| *b == GROUP_SEPARATOR)
+                .unwrap_or(rest.len() - ai_len),
+        };
+
+        let value_start = offset + ai_len;
+        elements.push(Gs1Element {
+            ai: &text[offset..value_start],
+            value: &text[value_start..value_start + value_len],
+        });
+
+        offset = value_start + value_len;
+        // Also allowed after predefined length values
+        if content.get(offset) == Some(&GROUP_SEPARATOR) {
+            offset += 1;
+        }
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements() {
+        let content = b"\x1d01095011010209171725010110ABC1\x1d21123";
+        let pairs: Vec<_> = parse_gs1(content)
+            .unwrap()
+            .into_iter()
+            .map(|element| (element.ai, element.value))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("01", "09501101020917"),
+                ("17", "250101"),
+                ("10", "ABC1"),
+                ("21", "123"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        // No AI starts with 27
+        assert_eq!(
+            parse_gs1(b"\x1d2712"),
+            Err(ValidationError::InvalidCharacter {
+                position: 1,
+                byte: b'2'
+            })
+        );
+        // A GTIN is 14 digits
+        assert_eq!(
+            parse_gs1(b"0112345"),
+            Err(ValidationError::InvalidLength(7))
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Macro PDF417 and Macro MicroPDF417 (`MacroPdf417`, `MacroMicroPdf`)
+//!
+//! Macro PDF spreads a file over up to 99999 symbols. With the scanner set
+//! to transmit every symbol as it is read (instead of buffering the whole
+//! file itself), each symbol arrives as its own `DecodeData` message and
+//! carries the Macro PDF control block in the escaped form of ISO 15438
+//! behind its data:
+//!
+//! - `\928`, the 5 digit segment index and the file ID, as digits
+//! - optional fields, each `\923`, a field designator digit and the value,
+//!   where designator 1 is the segment count
+//! - `\922` on the last segment
+//!
+//! Backslashes in the data itself are doubled. Segments can be scanned in
+//! any order.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::ValidationError;
+
+/// Field designator of the optional segment count field
+const SEGMENT_COUNT: u8 = b'1';
+
+/// A single symbol of a Macro PDF file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroPdfSegment {
+    /// 0 based position in the file
+    pub index: u32,
+    /// The file ID shared by all segments, as transmitted
+    pub file_id: String,
+    /// Number of segments, if the optional field is present
+    pub count: Option<u32>,
+    /// Whether this is the last segment of the file
+    pub last: bool,
+    /// The data, with doubled backslashes undone
+    pub data: Vec<u8>,
+}
+
+/// Splits the content of a Macro PDF symbol into data and control block
+///
+/// Fails if there is no control block, or it is malformed.
+pub fn parse_macro_pdf(
+    content: &[u8],
+) -> Result<MacroPdfSegment, ValidationError> {
+    let mut data = Vec::new();
+    let mut offset = 0;
+    let control = loop {
+        match content[offset..] {
+            [] => return Err(ValidationError::InvalidLength(content.len())),
+            [b'\\', b'\\', ..] => {
+                data.push(b'\\');
+                offset += 2;
+            }
+            [b'\\', b'9', b'2', b'8', ..] => break offset + 4,
+            [byte, ..] => {
+                data.push(byte);
+                offset += 1;
+            }
+        }
+    };
+
+    let invalid = |position: usize| ValidationError::InvalidCharacter {
+        position,
+        byte: content[position],
+    };
+    // Digits from `start` up to the next escape or the end
+    let digits = |start: usize| {
+        let end = content[start..]
+            .iter()
+            .position(|b| *b == b'\\')
+            .map_or(content.len(), |len| start + len);
+        match content[start..end].iter().position(|b| !b.is_ascii_digit()) {
+            Some(i) => Err(invalid(start + i)),
+            None => Ok(&content[start..end]),
+        }
+    };
+    let number = |digits: &[u8]| {
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(ValidationError::InvalidLength(content.len()))
+    };
+
+    let index = content
+        .get(control..control + 5)
+        .ok_or(ValidationError::InvalidLength(content.len()))?;
+    if let Some(i) = index.iter().position(|b| !b.is_ascii_digit()) {
+        return Err(invalid(control + i));
+    }
+    let index = number(index)?;
+    let file_id = digits(control + 5)?;
+    if file_id.is_empty() {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+
+    let mut segment = MacroPdfSegment {
+        index,
+        // Only digits at this point
+        file_id: String::from_utf8(file_id.to_vec()).unwrap(),
+        count: None,
+        last: false,
+        data,
+    };
+    let mut offset = control + 5 + file_id.len();
+    while offset < content.len() {
+        match content[offset..] {
+            [b'\\', b'9', b'2', b'2', ..] => {
+                segment.last = true;
+                offset += 4;
+            }
+            [b'\\', b'9', b'2', b'3', designator, ..] => {
+                let value = digits(offset + 5)?;
+                if designator == SEGMENT_COUNT {
+                    segment.count = Some(number(value)?);
+                }
+                offset += 5 + value.len();
+            }
+            _ => return Err(invalid(offset)),
+        }
+    }
+
+    Ok(segment)
+}
+
+/// Collects the segments of a Macro PDF file
+///
+/// Only one file is collected at a time: a segment of a different file, or
+/// one arriving after `timeout` has passed since the file was started,
+/// discards whatever was collected so far. The number of segments is taken
+/// from the segment count field, or else from the index of the last
+/// segment.
+pub struct MacroPdfCollator {
+    timeout: Duration,
+    file: Option<PendingFile>,
+}
+
+struct PendingFile {
+    file_id: String,
+    count: Option<u32>,
+    started: Instant,
+    segments: BTreeMap<u32, Vec<u8>>,
+}
+
+impl MacroPdfCollator {
+    pub fn new(timeout: Duration) -> Self {
+        MacroPdfCollator {
+            timeout,
+            file: None,
+        }
+    }
+
+    /// Adds a segment, returning the assembled file once all segments are
+    /// present
+    ///
+    /// Scanning a segment twice is harmless.
+    pub fn push(&mut self, segment: MacroPdfSegment) -> Option<Vec<u8>> {
+        let stale = self.file.as_ref().is_some_and(|file| {
+            file.file_id != segment.file_id
+                || file.started.elapsed() > self.timeout
+        });
+        if stale {
+            self.file = None;
+        }
+
+        let file = self.file.get_or_insert_with(|| PendingFile {
+            file_id: segment.file_id,
+            count: None,
+            started: Instant::now(),
+            segments: BTreeMap::new(),
+        });
+        if let Some(count) = segment.count {
+            file.count = Some(count);
+        } else if segment.last {
+            file.count.get_or_insert(segment.index + 1);
+        }
+        file.segments.insert(segment.index, segment.data);
+
+        // BTreeMap keys are unique and sorted, so the segments are complete
+        // if there are as many as expected and the last one is in place
+        let count = file.count?;
+        let complete = usize::try_from(count)
+            .is_ok_and(|count| file.segments.len() == count)
+            && file.segments.keys().next_back() == Some(&(count - 1));
+        if !complete {
+            return None;
+        }
+
+        let file = self.file.take().unwrap();
+        Some(file.segments.into_values().flatten().collect())
+    }
+
+    /// Whether a started file has been waiting longer than the timeout
+    pub fn is_expired(&self) -> bool {
+        self.file
+            .as_ref()
+            .is_some_and(|file| file.started.elapsed() > self.timeout)
+    }
+
+    /// Discards an incomplete file, like after `FLUSH_MACRO_PDF`
+    pub fn reset(&mut self) {
+        self.file = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_block() {
+        let segment = parse_macro_pdf(b"a\\\\b\\92800001123\\92312\\922");
+        assert_eq!(
+            segment,
+            Ok(MacroPdfSegment {
+                index: 1,
+                file_id: "123".to_string(),
+                count: Some(2),
+                last: true,
+                data: b"a\\b".to_vec(),
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_control_block() {
+        assert_eq!(
+            parse_macro_pdf(b"abc"),
+            Err(ValidationError::InvalidLength(3))
+        );
+        assert_eq!(
+            parse_macro_pdf(b"\\928000x1123"),
+            Err(ValidationError::InvalidCharacter {
+                position: 7,
+                byte: b'x'
+            })
+        );
+    }
+
+    #[test]
+    fn collate() {
+        let mut collator = MacroPdfCollator::new(Duration::from_secs(10));
+        let last = parse_macro_pdf(b"def\\92800001123\\922").unwrap();
+        let first = parse_macro_pdf(b"abc\\92800000123").unwrap();
+        let other = parse_macro_pdf(b"xyz\\92800000456").unwrap();
+
+        assert_eq!(collator.push(last.clone()), None);
+        // Starts over with file 456, which lacks its last segment
+        assert_eq!(collator.push(other), None);
+        assert_eq!(collator.push(first.clone()), None);
+        assert_eq!(collator.push(last), Some(b"abcdef".to_vec()));
+    }
+}
@@ -0,0 +1,123 @@
+//! Symbology-level validation and interpretation of decoded content.
+//!
+//! The scanner hands over the decoded label content more or less verbatim.
+//! The helpers in here check that content against the rules of the
+//! respective symbology and extract the parts applications care about.
+
+use std::fmt;
+
+pub mod code39;
+pub mod coupon;
+pub mod eci;
+pub mod gs1;
+pub mod macro_pdf;
+#[cfg(feature = "mrz")]
+pub mod mrz;
+pub mod multicode;
+pub mod ocr;
+#[cfg(feature = "postal")]
+pub mod postal;
+pub mod rfid;
+pub mod signature;
+pub mod stacked;
+pub mod structured_append;
+pub mod supplement;
+pub mod telepen;
+pub mod two_of_five;
+pub mod udi;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The content is empty or has a length the symbology doesn't allow
+    InvalidLength(usize),
+    /// A byte outside of the symbology's character set
+    InvalidCharacter { position: usize, byte: u8 },
+    /// The transmitted check character doesn't match the content
+    CheckDigitMismatch { expected: u8, found: u8 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidLength(len) => {
+                write!(f, "invalid content length {len}")
+            }
+            ValidationError::InvalidCharacter { position, byte } => {
+                write!(f, "invalid character {byte:#04x} at {position}")
+            }
+            ValidationError::CheckDigitMismatch { expected, found } => write!(
+                f,
+                "check character mismatch: expected '{}', found '{}'",
+                expected.escape_ascii(),
+                found.escape_ascii()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Makes sure `content` only consists of ASCII digits
+fn check_digits(content: &[u8]) -> Result<(), ValidationError> {
+    match content.iter().position(|b| !b.is_ascii_digit()) {
+        Some(position) => Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Modulo 10 check digit with alternating weights of 3 and 1, starting
+/// with 3 on the rightmost digit (as used by UPC/EAN and the 2 of 5 family)
+///
+/// `digits` must only contain ASCII digits.
+pub fn mod10_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| {
+            let value = u32::from(digit - b'0');
+            if i % 2 == 0 {
+                value * 3
+            } else {
+                value
+            }
+        })
+        .sum();
+
+    b'0' + ((10 - sum % 10) % 10) as u8
+}
+
+/// Check digit completing the plain digit sum to a multiple of 10 (as used
+/// by POSTNET, PLANET and Korean 3 of 5)
+///
+/// `digits` must only contain ASCII digits.
+pub fn digit_sum_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits.iter().map(|digit| u32::from(digit - b'0')).sum();
+    b'0' + ((10 - sum % 10) % 10) as u8
+}
+
+/// Splits off and verifies a trailing check character
+fn split_check(
+    content: &[u8],
+    calc: impl FnOnce(&[u8]) -> u8,
+) -> Result<&[u8], ValidationError> {
+    let [data @ .., found] = content else {
+        return Err(ValidationError::InvalidLength(content.len()));
+    };
+    if data.is_empty() {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+
+    let expected = calc(data);
+    if expected != *found {
+        return Err(ValidationError::CheckDigitMismatch {
+            expected,
+            found: *found,
+        });
+    }
+
+    Ok(data)
+}
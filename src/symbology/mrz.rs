@@ -0,0 +1,240 @@
+//! Machine readable zones of travel documents (ICAO 9303)
+//!
+//! Covers the TD1 format of ID cards (3 lines of 30 characters) and the TD3
+//! format of passports (2 lines of 44 characters), read as `OcrB` content.
+//! Fields are stripped of their `<` fillers, dates are left as YYMMDD.
+//! Document numbers longer than 9 characters, which TD1 continues in the
+//! optional data, are not supported.
+
+use std::ops::Range;
+
+use super::ocr::validate_ocr_b;
+use super::ValidationError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MrzFormat {
+    Td1,
+    Td3,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mrz<'a> {
+    pub format: MrzFormat,
+    /// `P` for passports, `I`, `A` or `C` for ID cards, possibly followed
+    /// by the document type
+    pub document_code: &'a str,
+    pub issuing_state: &'a str,
+    pub document_number: &'a str,
+    pub nationality: &'a str,
+    pub birth_date: &'a str,
+    /// `M`, `F` or `<` for unspecified
+    pub sex: char,
+    pub expiry_date: &'a str,
+    /// Personal number (TD3), or the optional data of the first line (TD1)
+    pub optional: &'a str,
+    /// Optional data of the second line, always empty for TD3
+    pub optional2: &'a str,
+    pub surname: String,
+    pub given_names: String,
+}
+
+/// Parses a TD1 or TD3 machine readable zone, verifying all check digits
+pub fn parse_mrz(content: &[u8]) -> Result<Mrz<'_>, ValidationError> {
+    let text = validate_ocr_b(content)?;
+
+    let lines: Vec<&str> = text
+        .split(['\r', '\n'])
+        .filter(|line| !line.is_empty())
+        .collect();
+    for line in &lines {
+        let offset = line.as_ptr() as usize - text.as_ptr() as usize;
+        let invalid = line.bytes().position(|byte| {
+            !(byte.is_ascii_uppercase()
+                || byte.is_ascii_digit()
+                || byte == b'<')
+        });
+        if let Some(position) = invalid {
+            return Err(ValidationError::InvalidCharacter {
+                position: offset + position,
+                byte: line.as_bytes()[position],
+            });
+        }
+    }
+
+    match lines[..] {
+        [line1, line2] if line1.len() == 44 && line2.len() == 44 => {
+            parse_td3(line1, line2)
+        }
+        [line1, line2, line3]
+            if [line1, line2, line3].iter().all(|line| line.len() == 30) =>
+        {
+            parse_td1(line1, line2, line3)
+        }
+        _ => Err(ValidationError::InvalidLength(content.len())),
+    }
+}
+
+fn parse_td3<'a>(
+    line1: &'a str,
+    line2: &'a str,
+) -> Result<Mrz<'a>, ValidationError> {
+    verify(&line2[0..9], line2.as_bytes()[9])?;
+    verify(&line2[13..19], line2.as_bytes()[19])?;
+    verify(&line2[21..27], line2.as_bytes()[27])?;
+    // An empty personal number may have a filler as check digit
+    if !(line2[28..43].bytes().all(|byte| byte == b'<')) {
+        verify(&line2[28..42], line2.as_bytes()[42])?;
+    }
+    let composite = [&line2[0..10], &line2[13..20], &line2[21..43]].concat();
+    verify(&composite, line2.as_bytes()[43])?;
+
+    let (surname, given_names) = names(&line1[5..44]);
+    Ok(Mrz {
+        format: MrzFormat::Td3,
+        document_code: field(line1, 0..2),
+        issuing_state: field(line1, 2..5),
+        document_number: field(line2, 0..9),
+        nationality: field(line2, 10..13),
+        birth_date: field(line2, 13..19),
+        sex: char::from(line2.as_bytes()[20]),
+        expiry_date: field(line2, 21..27),
+        optional: field(line2, 28..42),
+        optional2: "",
+        surname,
+        given_names,
+    })
+}
+
+fn parse_td1<'a>(
+    line1: &'a str,
+    line2: &'a str,
+    line3: &'a str,
+) -> Result<Mrz<'a>, ValidationError> {
+    verify(&line1[5..14], line1.as_bytes()[14])?;
+    verify(&line2[0..6], line2.as_bytes()[6])?;
+    verify(&line2[8..14], line2.as_bytes()[14])?;
+    let composite =
+        [&line1[5..30], &line2[0..7], &line2[8..15], &line2[18..29]].concat();
+    verify(&composite, line2.as_bytes()[29])?;
+
+    let (surname, given_names) = names(line3);
+    Ok(Mrz {
+        format: MrzFormat::Td1,
+        document_code: field(line1, 0..2),
+        issuing_state: field(line1, 2..5),
+        document_number: field(line1, 5..14),
+        nationality: field(line2, 15..18),
+        birth_date: field(line2, 0..6),
+        sex: char::from(line2.as_bytes()[7]),
+        expiry_date: field(line2, 8..14),
+        optional: field(line1, 15..30),
+        optional2: field(line2, 18..29),
+        surname,
+        given_names,
+    })
+}
+
+fn field(line: &str, range: Range<usize>) -> &str {
+    line[range].trim_end_matches('<')
+}
+
+/// Splits the name field into surname and given names, separated by `<<`
+/// with `<` between the words of either
+fn names(field: &str) -> (String, String) {
+    let (surname, given_names) = field.split_once("<<").unwrap_or((field, ""));
+    let words = |part: &str| {
+        part.split('<')
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    (words(surname), words(given_names))
+}
+
+/// ICAO 9303 check digit with repeating weights of 7, 3 and 1
+fn check_digit(field: &str) -> u8 {
+    let sum: u32 = field
+        .bytes()
+        .zip([7, 3, 1].into_iter().cycle())
+        .map(|(byte, weight)| {
+            let value = match byte {
+                b'0'..=b'9' => byte - b'0',
+                b'A'..=b'Z' => byte - b'A' + 10,
+                // Filler
+                _ => 0,
+            };
+            u32::from(value) * weight
+        })
+        .sum();
+
+    b'0' + (sum % 10) as u8
+}
+
+fn verify(field: &str, found: u8) -> Result<(), ValidationError> {
+    let expected = check_digit(field);
+    if expected != found {
+        return Err(ValidationError::CheckDigitMismatch { expected, found });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The specimens of ICAO 9303
+    const TD3: &str = "P<UTOERIKSSON<<ANNA<MARIA<<<<<<<<<<<<<<<<<<<\r\
+                       L898902C36UTO7408122F1204159ZE184226B<<<<<10";
+    const TD1: &str = "I<UTOD231458907<<<<<<<<<<<<<<<\r\
+                       7408122F1204159UTO<<<<<<<<<<<6\r\
+                       ERIKSSON<<ANNA<MARIA<<<<<<<<<<";
+
+    #[test]
+    fn td3() {
+        let mrz = parse_mrz(TD3.as_bytes()).unwrap();
+        assert_eq!(mrz.format, MrzFormat::Td3);
+        assert_eq!(mrz.document_code, "P");
+        assert_eq!(mrz.document_number, "L898902C3");
+        assert_eq!(mrz.birth_date, "740812");
+        assert_eq!(mrz.sex, 'F');
+        assert_eq!(mrz.optional, "ZE184226B");
+        assert_eq!(mrz.surname, "ERIKSSON");
+        assert_eq!(mrz.given_names, "ANNA MARIA");
+    }
+
+    #[test]
+    fn td1() {
+        let mrz = parse_mrz(TD1.as_bytes()).unwrap();
+        assert_eq!(mrz.format, MrzFormat::Td1);
+        assert_eq!(mrz.document_code, "I");
+        assert_eq!(mrz.document_number, "D23145890");
+        assert_eq!(mrz.nationality, "UTO");
+        assert_eq!(mrz.expiry_date, "120415");
+        assert_eq!(mrz.given_names, "ANNA MARIA");
+    }
+
+    #[test]
+    fn rejected() {
+        let wrong_check = TD3.replace("C36", "C37");
+        assert_eq!(
+            parse_mrz(wrong_check.as_bytes()),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'6',
+                found: b'7'
+            })
+        );
+        assert_eq!(
+            parse_mrz(&TD1.as_bytes()[..61]),
+            Err(ValidationError::InvalidLength(61))
+        );
+        assert_eq!(
+            parse_mrz(b"P<UTOeriksson"),
+            Err(ValidationError::InvalidCharacter {
+                position: 5,
+                byte: b'e'
+            })
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Multicode (`Multicode`) output
+//!
+//! With multicode decoding, the scanner reads every barcode on a label in
+//! one go and transmits them in a single message, separated by the
+//! delimiter configured on the scanner. If code type transmission is
+//! enabled, each barcode's data is preceded by its content type byte.
+
+use crate::ContentType;
+
+/// Splits a `Multicode` payload into the individual barcodes
+///
+/// With `typed` set, the first byte of each barcode is read as its content
+/// type. Unknown content types and untyped barcodes come back as `None`.
+pub fn parse_multicode(
+    content: &[u8],
+    delimiter: u8,
+    typed: bool,
+) -> Vec<(Option<ContentType>, Vec<u8>)> {
+    content
+        .split(|byte| *byte == delimiter)
+        .filter(|code| !code.is_empty())
+        .map(|code| match code {
+            [content_type, data @ ..] if typed => {
+                (ContentType::try_from(*content_type).ok(), data.to_vec())
+            }
+            _ => (None, code.to_vec()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed() {
+        assert_eq!(
+            parse_multicode(b"\x0b4006381333931;\x03abc;", b';', true),
+            [
+                (Some(ContentType::Ean13), b"4006381333931".to_vec()),
+                (Some(ContentType::Code128), b"abc".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_or_untyped() {
+        assert_eq!(
+            parse_multicode(b"\x00abc", b';', true),
+            [(None, b"abc".to_vec())]
+        );
+        assert_eq!(
+            parse_multicode(b"\x03abc;;def", b';', false),
+            [(None, b"\x03abc".to_vec()), (None, b"def".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! OCR-B text (`OcrB`)
+//!
+//! Scanners with OCR support read lines of text printed in the OCR-B font,
+//! like the machine readable zone of travel documents or the code line of
+//! cheques, and transmit them as ASCII with the lines separated by CR.
+
+use super::ValidationError;
+
+/// Validates `OcrB` content and returns it as text
+///
+/// OCR-B covers printable ASCII, so that and line breaks are all that is
+/// accepted.
+pub fn validate_ocr_b(content: &[u8]) -> Result<&str, ValidationError> {
+    if content.is_empty() {
+        return Err(ValidationError::InvalidLength(0));
+    }
+
+    let invalid = content.iter().position(|byte| {
+        !(byte.is_ascii_graphic() || matches!(byte, b' ' | b'\r' | b'\n'))
+    });
+    if let Some(position) = invalid {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+
+    // Only ASCII at this point
+    Ok(std::str::from_utf8(content).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ocr_b() {
+        assert_eq!(validate_ocr_b(b"A1 <B>\r2"), Ok("A1 <B>\r2"));
+        assert_eq!(
+            validate_ocr_b(b"A\tB"),
+            Err(ValidationError::InvalidCharacter {
+                position: 1,
+                byte: b'\t'
+            })
+        );
+        assert_eq!(validate_ocr_b(b""), Err(ValidationError::InvalidLength(0)));
+    }
+}
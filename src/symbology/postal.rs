@@ -0,0 +1,453 @@
+//! Postal symbologies
+//!
+//! Mail sortation cares about the routing data in these, so the parsers
+//! split the content into its fields. The content is expected the way the
+//! scanner transmits it: digits for the US codes, and the alphanumeric
+//! characters the bars encode for the other 4 state codes.
+
+use super::{
+    check_digits, digit_sum_check_digit, split_check, ValidationError,
+};
+
+/// US POSTNET
+#[derive(Debug, PartialEq, Eq)]
+pub struct Postnet<'a> {
+    pub zip: &'a str,
+    pub plus4: Option<&'a str>,
+    pub delivery_point: Option<&'a str>,
+}
+
+/// Parses `PostnetUs` content of 5, 9 or 11 digits
+///
+/// With `check_digit` set, a trailing check digit is expected (the scanner
+/// transmits it when "Transmit US Postal Check Digit" is enabled), verified
+/// and stripped.
+pub fn parse_postnet(
+    content: &[u8],
+    check_digit: bool,
+) -> Result<Postnet<'_>, ValidationError> {
+    let digits = postal_digits(content, check_digit)?;
+
+    let (zip, plus4, delivery_point) = match digits.len() {
+        5 => (digits, None, None),
+        9 => (&digits[..5], Some(&digits[5..]), None),
+        11 => (&digits[..5], Some(&digits[5..9]), Some(&digits[9..])),
+        len => return Err(ValidationError::InvalidLength(len)),
+    };
+
+    Ok(Postnet {
+        zip,
+        plus4,
+        delivery_point,
+    })
+}
+
+/// Parses `PlanetUs` content of 11 or 13 digits into the tracking code
+///
+/// `check_digit` as for `parse_postnet`.
+pub fn parse_planet(
+    content: &[u8],
+    check_digit: bool,
+) -> Result<&str, ValidationError> {
+    let digits = postal_digits(content, check_digit)?;
+
+    match digits.len() {
+        11 | 13 => Ok(digits),
+        len => Err(ValidationError::InvalidLength(len)),
+    }
+}
+
+fn postal_digits(
+    content: &[u8],
+    check_digit: bool,
+) -> Result<&str, ValidationError> {
+    check_digits(content)?;
+    let digits = if check_digit {
+        split_check(content, digit_sum_check_digit)?
+    } else {
+        content
+    };
+
+    // Only ASCII digits at this point
+    Ok(std::str::from_utf8(digits).unwrap())
+}
+
+/// US Intelligent Mail barcode (`FourStateUs`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct IntelligentMail<'a> {
+    pub barcode_id: &'a str,
+    pub service_type: &'a str,
+    /// 9 digits if it starts with a 9, 6 digits otherwise
+    pub mailer_id: &'a str,
+    pub serial_number: &'a str,
+    /// The routing code, which is a ZIP code in POSTNET layout
+    pub routing: Option<Postnet<'a>>,
+}
+
+/// Parses the 20 digit tracking code and the optional 5, 9 or 11 digit
+/// routing code of an Intelligent Mail barcode
+pub fn parse_intelligent_mail(
+    content: &[u8],
+) -> Result<IntelligentMail<'_>, ValidationError> {
+    check_digits(content)?;
+    if content.len() < 20 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    let (tracking, routing) = content.split_at(20);
+    // Only ASCII digits at this point
+    let tracking = std::str::from_utf8(tracking).unwrap();
+
+    let mailer_id_len = if tracking.as_bytes()[5] == b'9' { 9 } else { 6 };
+    let (mailer_id, serial_number) = tracking[5..].split_at(mailer_id_len);
+
+    let routing = match routing.len() {
+        0 => None,
+        _ => Some(parse_postnet(routing, false)?),
+    };
+
+    Ok(IntelligentMail {
+        barcode_id: &tracking[..2],
+        service_type: &tracking[2..5],
+        mailer_id,
+        serial_number,
+        routing,
+    })
+}
+
+/// Royal Mail 4 State Customer Code (`PostalUk`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct RoyalMail<'a> {
+    /// Postcode without the space between outward and inward code
+    pub postcode: &'a str,
+    /// Delivery point suffix, a digit and a letter
+    pub delivery_point: Option<&'a str>,
+}
+
+const RM4SCC_CHARSET: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// RM4SCC check character
+///
+/// Each character sits in a 6x6 grid. The check character is the one at the
+/// row and column of the respective sums over the content modulo 6.
+fn rm4scc_check_char(data: &[u8]) -> u8 {
+    let (rows, columns) = data
+        .iter()
+        .filter_map(|c| RM4SCC_CHARSET.iter().position(|x| x == c))
+        .fold((0, 0), |(rows, columns), index| {
+            (rows + index / 6 + 1, columns + index % 6 + 1)
+        });
+
+    // Sums are over 1 based rows and columns, where a remainder of 0 is 6
+    RM4SCC_CHARSET[(rows + 5) % 6 * 6 + (columns + 5) % 6]
+}
+
+/// Parses `PostalUk` content
+///
+/// With `check_char` set, the trailing RM4SCC check character is verified
+/// and stripped.
+pub fn parse_royal_mail(
+    content: &[u8],
+    check_char: bool,
+) -> Result<RoyalMail<'_>, ValidationError> {
+    if let Some(position) =
+        content.iter().position(|c| !RM4SCC_CHARSET.contains(c))
+    {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+    let data = if check_char {
+        split_check(content, rm4scc_check_char)?
+    } else {
+        content
+    };
+    // Only ASCII alphanumerics at this point
+    let data = std::str::from_utf8(data).unwrap();
+
+    // UK postcodes are 5 to 7 characters, the suffix is digit and letter
+    let (postcode, delivery_point) = match data.as_bytes() {
+        [.., digit, letter]
+            if data.len() >= 7
+                && digit.is_ascii_digit()
+                && letter.is_ascii_alphabetic() =>
+        {
+            let (postcode, suffix) = data.split_at(data.len() - 2);
+            (postcode, Some(suffix))
+        }
+        _ if (5..=7).contains(&data.len()) => (data, None),
+        _ => return Err(ValidationError::InvalidLength(data.len())),
+    };
+
+    Ok(RoyalMail {
+        postcode,
+        delivery_point,
+    })
+}
+
+/// Australia Post 4 state customer barcode (`PostalAus`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct AustraliaPost<'a> {
+    /// Format control code, telling the barcode length and layout
+    pub fcc: &'a str,
+    /// Delivery point identifier
+    pub dpid: &'a str,
+    /// Customer information, empty for the standard customer barcode
+    pub customer_info: &'a str,
+}
+
+/// Parses `PostalAus` content into FCC, DPID and customer information
+pub fn parse_australia_post(
+    content: &[u8],
+) -> Result<AustraliaPost<'_>, ValidationError> {
+    if content.len() < 10 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    check_digits(&content[..10])?;
+    let content = ascii_str(content)?;
+
+    Ok(AustraliaPost {
+        fcc: &content[..2],
+        dpid: &content[2..10],
+        customer_info: &content[10..],
+    })
+}
+
+/// Japan Post customer barcode (`PostalJp`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct JapanPost<'a> {
+    /// 7 digit postal code
+    pub postcode: &'a str,
+    /// Address indicator, digits, letters and hyphens
+    pub address: &'a str,
+}
+
+pub fn parse_japan_post(
+    content: &[u8],
+) -> Result<JapanPost<'_>, ValidationError> {
+    if content.len() < 7 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    check_digits(&content[..7])?;
+    let content = ascii_str(content)?;
+
+    Ok(JapanPost {
+        postcode: &content[..7],
+        address: &content[7..],
+    })
+}
+
+/// Dutch KIX code (`PostalNl`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Kix<'a> {
+    /// 4 digits and 2 letters
+    pub postcode: &'a str,
+    pub house_number: &'a str,
+    /// House number addition, following the `X` separator
+    pub addition: Option<&'a str>,
+}
+
+pub fn parse_kix(content: &[u8]) -> Result<Kix<'_>, ValidationError> {
+    if content.len() < 7 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+    check_digits(&content[..4])?;
+    let content = ascii_str(content)?;
+
+    let (house_number, addition) = match content[6..].split_once('X') {
+        Some((house_number, addition)) => (house_number, Some(addition)),
+        None => (&content[6..], None),
+    };
+
+    Ok(Kix {
+        postcode: &content[..6],
+        house_number,
+        addition,
+    })
+}
+
+/// Canada Post Postbar (`PostbarCa`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Postbar<'a> {
+    /// The leading postal code, if the content starts with one
+    pub postal_code: Option<&'a str>,
+    pub content: &'a str,
+}
+
+pub fn parse_postbar(content: &[u8]) -> Result<Postbar<'_>, ValidationError> {
+    let content = ascii_str(content)?;
+
+    // Canadian postal codes alternate letters and digits: A1A1A1
+    let postal_code = content.get(..6).filter(|code| {
+        code.bytes().enumerate().all(|(i, c)| match i % 2 {
+            0 => c.is_ascii_uppercase(),
+            _ => c.is_ascii_digit(),
+        })
+    });
+
+    Ok(Postbar {
+        postal_code,
+        content,
+    })
+}
+
+fn ascii_str(content: &[u8]) -> Result<&str, ValidationError> {
+    match content.iter().position(|c| !c.is_ascii_graphic()) {
+        Some(position) => Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        }),
+        // Only ASCII at this point
+        None => Ok(std::str::from_utf8(content).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn postnet() {
+        assert_eq!(
+            parse_postnet(b"123456789", false),
+            Ok(Postnet {
+                zip: "12345",
+                plus4: Some("6789"),
+                delivery_point: None,
+            })
+        );
+        assert_eq!(parse_postnet(b"123455", true).unwrap().zip, "12345");
+        assert_eq!(
+            parse_postnet(b"123450", true),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'5',
+                found: b'0'
+            })
+        );
+        assert_eq!(
+            parse_postnet(b"1234567", false),
+            Err(ValidationError::InvalidLength(7))
+        );
+    }
+
+    #[test]
+    fn planet() {
+        assert_eq!(parse_planet(b"12345678901", false), Ok("12345678901"));
+        assert_eq!(
+            parse_planet(b"123456789012", false),
+            Err(ValidationError::InvalidLength(12))
+        );
+    }
+
+    #[test]
+    fn intelligent_mail() {
+        let content = b"0123456789012345678912345678901";
+        let imb = parse_intelligent_mail(content).unwrap();
+        assert_eq!(imb.barcode_id, "01");
+        assert_eq!(imb.service_type, "234");
+        assert_eq!(imb.mailer_id, "567890");
+        assert_eq!(imb.serial_number, "123456789");
+        assert_eq!(
+            imb.routing,
+            Some(Postnet {
+                zip: "12345",
+                plus4: Some("6789"),
+                delivery_point: Some("01"),
+            })
+        );
+        assert_eq!(
+            parse_intelligent_mail(&content[..19]),
+            Err(ValidationError::InvalidLength(19))
+        );
+    }
+
+    #[test]
+    fn royal_mail() {
+        assert_eq!(
+            parse_royal_mail(b"LU178XE2AE", true),
+            Ok(RoyalMail {
+                postcode: "LU178XE",
+                delivery_point: Some("2A"),
+            })
+        );
+        assert_eq!(
+            parse_royal_mail(b"LU178XE2AA", true),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'E',
+                found: b'A'
+            })
+        );
+        assert_eq!(
+            parse_royal_mail(b"lu178xe", false),
+            Err(ValidationError::InvalidCharacter {
+                position: 0,
+                byte: b'l'
+            })
+        );
+    }
+
+    #[test]
+    fn australia_post() {
+        assert_eq!(
+            parse_australia_post(b"1139987520"),
+            Ok(AustraliaPost {
+                fcc: "11",
+                dpid: "39987520",
+                customer_info: "",
+            })
+        );
+        assert_eq!(
+            parse_australia_post(b"11399875"),
+            Err(ValidationError::InvalidLength(8))
+        );
+    }
+
+    #[test]
+    fn japan_post() {
+        assert_eq!(
+            parse_japan_post(b"12345671-2-3"),
+            Ok(JapanPost {
+                postcode: "1234567",
+                address: "1-2-3",
+            })
+        );
+        assert_eq!(
+            parse_japan_post(b"123A567"),
+            Err(ValidationError::InvalidCharacter {
+                position: 3,
+                byte: b'A'
+            })
+        );
+    }
+
+    #[test]
+    fn kix() {
+        assert_eq!(
+            parse_kix(b"2500GG30X2"),
+            Ok(Kix {
+                postcode: "2500GG",
+                house_number: "30",
+                addition: Some("2"),
+            })
+        );
+        assert_eq!(parse_kix(b"250"), Err(ValidationError::InvalidLength(3)));
+    }
+
+    #[test]
+    fn postbar() {
+        assert_eq!(
+            parse_postbar(b"K1A0B1REST"),
+            Ok(Postbar {
+                postal_code: Some("K1A0B1"),
+                content: "K1A0B1REST",
+            })
+        );
+        assert_eq!(
+            parse_postbar(b"K1A 0B1"),
+            Err(ValidationError::InvalidCharacter {
+                position: 3,
+                byte: b' '
+            })
+        );
+    }
+}
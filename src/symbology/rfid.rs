@@ -0,0 +1,87 @@
+//! RFID tags read by combined barcode and RFID scanners (`RfidRaw`,
+//! `RfidURI`)
+//!
+//! Depending on the scanner's settings, a tag is transmitted either as the
+//! binary contents of its EPC memory bank or as the EPC decoded into its
+//! pure identity URI, like `urn:epc:id:sgtin:0614141.812345.6789`.
+
+use super::ValidationError;
+use crate::ContentType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfidTag<'a> {
+    /// EPC memory bank contents
+    Raw(&'a [u8]),
+    Uri(&'a str),
+}
+
+/// Parses the content of an `RfidRaw` or `RfidURI` message
+///
+/// Returns `None` for other content types.
+pub fn parse_rfid(
+    content_type: ContentType,
+    content: &[u8],
+) -> Option<Result<RfidTag<'_>, ValidationError>> {
+    let tag = match content_type {
+        ContentType::RfidRaw => {
+            if content.is_empty() {
+                Err(ValidationError::InvalidLength(0))
+            } else {
+                Ok(RfidTag::Raw(content))
+            }
+        }
+        ContentType::RfidURI => parse_uri(content).map(RfidTag::Uri),
+        _ => return None,
+    };
+
+    Some(tag)
+}
+
+fn parse_uri(content: &[u8]) -> Result<&str, ValidationError> {
+    if content.is_empty() {
+        return Err(ValidationError::InvalidLength(0));
+    }
+    if let Some(position) = content.iter().position(|b| !b.is_ascii_graphic()) {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+
+    // Only ASCII at this point
+    Ok(std::str::from_utf8(content).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        let uri = b"urn:epc:id:sgtin:0614141.812345.6789";
+        assert_eq!(
+            parse_rfid(ContentType::RfidURI, uri),
+            Some(Ok(RfidTag::Uri("urn:epc:id:sgtin:0614141.812345.6789")))
+        );
+        assert_eq!(
+            parse_rfid(ContentType::RfidRaw, &[0x30, 0x14]),
+            Some(Ok(RfidTag::Raw(&[0x30, 0x14])))
+        );
+        assert_eq!(parse_rfid(ContentType::Code128, uri), None);
+    }
+
+    #[test]
+    fn rejected() {
+        assert_eq!(
+            parse_rfid(ContentType::RfidURI, b"urn:epc id"),
+            Some(Err(ValidationError::InvalidCharacter {
+                position: 7,
+                byte: b' '
+            }))
+        );
+        assert_eq!(
+            parse_rfid(ContentType::RfidRaw, b""),
+            Some(Err(ValidationError::InvalidLength(0)))
+        );
+    }
+}
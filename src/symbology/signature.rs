@@ -0,0 +1,76 @@
+//! Signature capture (`Signature`)
+//!
+//! Scanners with signature capture read the signature box of a form and
+//! transmit it as an image: a format byte (1 for JPEG, 3 for BMP, 4 for
+//! TIFF), a type byte telling the kind of signature box, the image length
+//! as big endian u32 and the image file. Large images take many packets,
+//! so the content is expected reassembled, see `MessageAssembler`.
+
+use super::ValidationError;
+use crate::CapturedImage;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureCapture {
+    /// Kind of signature box captured, as configured on the scanner
+    pub signature_type: u8,
+    pub image: CapturedImage,
+}
+
+/// Parses the content of a `Signature` message
+///
+/// Bytes following the image are ignored.
+pub fn parse_signature(
+    content: &[u8],
+) -> Result<SignatureCapture, ValidationError> {
+    let [format, signature_type, l1, l2, l3, l4, rest @ ..] = content else {
+        return Err(ValidationError::InvalidLength(content.len()));
+    };
+    if !matches!(format, 1 | 3 | 4) {
+        return Err(ValidationError::InvalidCharacter {
+            position: 0,
+            byte: *format,
+        });
+    }
+
+    let len = u32::from_be_bytes([*l1, *l2, *l3, *l4]);
+    let image = usize::try_from(len)
+        .ok()
+        .and_then(|len| rest.get(..len))
+        .ok_or(ValidationError::InvalidLength(content.len()))?;
+
+    Ok(SignatureCapture {
+        signature_type: *signature_type,
+        image: CapturedImage::parse(image.to_vec()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFormat;
+
+    #[test]
+    fn jpeg() {
+        let content = [1, 2, 0, 0, 0, 4, 0xff, 0xd8, 0xff, 0xd9, 0x00];
+        let signature = parse_signature(&content).unwrap();
+        assert_eq!(signature.signature_type, 2);
+        assert_eq!(signature.image.format, ImageFormat::Jpeg);
+        assert_eq!(signature.image.data, [0xff, 0xd8, 0xff, 0xd9]);
+    }
+
+    #[test]
+    fn rejected() {
+        assert_eq!(
+            parse_signature(&[2, 2, 0, 0, 0, 0]),
+            Err(ValidationError::InvalidCharacter {
+                position: 0,
+                byte: 2
+            })
+        );
+        // Shorter than the length claims
+        assert_eq!(
+            parse_signature(&[1, 2, 0, 0, 0, 4, 0xff, 0xd8]),
+            Err(ValidationError::InvalidLength(8))
+        );
+    }
+}
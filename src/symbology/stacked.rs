@@ -0,0 +1,49 @@
+//! The stacked Code 49 and Code 16K
+//!
+//! The scanner resolves the shift and mode characters of both, what
+//! arrives is the character data. Code 49 is limited to ASCII. Code 16K,
+//! like Code 128, reaches the upper half of ISO 8859-1 through FNC4, so its
+//! bytes above 0x7f are Latin-1 characters rather than UTF-8 and come out
+//! garbled when converted as such.
+
+use super::ValidationError;
+
+/// Validates `Code49` content, which can only be ASCII
+pub fn decode_code49(content: &[u8]) -> Result<&str, ValidationError> {
+    if let Some(position) = content.iter().position(|b| !b.is_ascii()) {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+
+    // Only ASCII at this point
+    Ok(std::str::from_utf8(content).unwrap())
+}
+
+/// Converts `Code16K` content from ISO 8859-1
+pub fn decode_code16k(content: &[u8]) -> String {
+    content.iter().copied().map(char::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code49() {
+        assert_eq!(decode_code49(b"AB-12"), Ok("AB-12"));
+        assert_eq!(
+            decode_code49(b"AB\xe912"),
+            Err(ValidationError::InvalidCharacter {
+                position: 2,
+                byte: 0xe9
+            })
+        );
+    }
+
+    #[test]
+    fn code16k() {
+        assert_eq!(decode_code16k(b"caf\xe9"), "café");
+    }
+}
@@ -0,0 +1,242 @@
+//! QR and Data Matrix structured append
+//!
+//! Both symbologies can spread content over up to 16 symbols. With the
+//! scanner set to transmit structured append symbols as they are read
+//! (instead of buffering the set itself), each symbol arrives as its own
+//! `DecodeData` message, led by the symbology's structured append header:
+//!
+//! - QR: one byte with the 0 based position in the high and the symbol count
+//!   minus one in the low nibble, then the parity byte, which is the XOR of
+//!   every data byte of the complete content.
+//! - Data Matrix: one byte with the 0 based position in the high nibble and
+//!   17 minus the symbol count in the low nibble, then the two byte file ID
+//!   shared by the set.
+//!
+//! Symbols of a set can be scanned in any order.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::ValidationError;
+
+/// What ties the symbols of a set together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendId {
+    /// QR parity over the complete content
+    Parity(u8),
+    /// Data Matrix file ID
+    FileId(u16),
+}
+
+/// A single symbol of a structured append set
+#[derive(Debug, PartialEq, Eq)]
+pub struct AppendSymbol<'a> {
+    /// 0 based position in the set
+    pub position: u8,
+    pub total: u8,
+    pub id: AppendId,
+    pub data: &'a [u8],
+}
+
+impl<'a> AppendSymbol<'a> {
+    pub fn from_qr(content: &'a [u8]) -> Result<Self, ValidationError> {
+        let [header, parity, data @ ..] = content else {
+            return Err(ValidationError::InvalidLength(content.len()));
+        };
+
+        Self::new(
+            *header,
+            header >> 4,
+            (header & 0x0f) + 1,
+            AppendId::Parity(*parity),
+            data,
+        )
+    }
+
+    pub fn from_data_matrix(
+        content: &'a [u8],
+    ) -> Result<Self, ValidationError> {
+        let [header, id1, id2, data @ ..] = content else {
+            return Err(ValidationError::InvalidLength(content.len()));
+        };
+        let total = 17u8.saturating_sub(header & 0x0f);
+
+        Self::new(
+            *header,
+            header >> 4,
+            total,
+            AppendId::FileId(u16::from_be_bytes([*id1, *id2])),
+            data,
+        )
+    }
+
+    fn new(
+        header: u8,
+        position: u8,
+        total: u8,
+        id: AppendId,
+        data: &'a [u8],
+    ) -> Result<Self, ValidationError> {
+        if !(2..=16).contains(&total) || position >= total {
+            return Err(ValidationError::InvalidCharacter {
+                position: 0,
+                byte: header,
+            });
+        }
+
+        Ok(AppendSymbol {
+            position,
+            total,
+            id,
+            data,
+        })
+    }
+}
+
+/// Collects the symbols of a structured append set
+///
+/// Only one set is collected at a time: a symbol of a different set, or
+/// one arriving after `timeout` has passed since the set was started,
+/// discards whatever was collected so far.
+pub struct StructuredAppend {
+    timeout: Duration,
+    set: Option<PendingSet>,
+}
+
+struct PendingSet {
+    id: AppendId,
+    total: u8,
+    started: Instant,
+    symbols: BTreeMap<u8, Vec<u8>>,
+}
+
+impl StructuredAppend {
+    pub fn new(timeout: Duration) -> Self {
+        StructuredAppend { timeout, set: None }
+    }
+
+    /// Adds a symbol, returning the combined content once the set is
+    /// complete
+    ///
+    /// Scanning a symbol twice is harmless. For QR, the parity of the
+    /// combined content is verified.
+    pub fn push(
+        &mut self,
+        symbol: AppendSymbol,
+    ) -> Result<Option<Vec<u8>>, ValidationError> {
+        let stale = self.set.as_ref().is_some_and(|set| {
+            set.id != symbol.id
+                || set.total != symbol.total
+                || set.started.elapsed() > self.timeout
+        });
+        if stale {
+            self.set = None;
+        }
+
+        let set = self.set.get_or_insert_with(|| PendingSet {
+            id: symbol.id,
+            total: symbol.total,
+            started: Instant::now(),
+            symbols: BTreeMap::new(),
+        });
+        set.symbols.insert(symbol.position, symbol.data.to_vec());
+
+        if set.symbols.len() < usize::from(set.total) {
+            return Ok(None);
+        }
+
+        // Complete, and BTreeMap iterates in position order
+        let set = self.set.take().unwrap();
+        let content: Vec<u8> = set.symbols.into_values().flatten().collect();
+
+        if let AppendId::Parity(expected) = set.id {
+            let found = content.iter().fold(0, |parity, byte| parity ^ byte);
+            if found != expected {
+                return Err(ValidationError::CheckDigitMismatch {
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        Ok(Some(content))
+    }
+
+    /// Whether a started set has been waiting longer than the timeout
+    pub fn is_expired(&self) -> bool {
+        self.set
+            .as_ref()
+            .is_some_and(|set| set.started.elapsed() > self.timeout)
+    }
+
+    /// Discards an incomplete set
+    pub fn reset(&mut self) {
+        self.set = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn qr_set() {
+        // Parity of "abcd"
+        let second = AppendSymbol::from_qr(b"\x11\x04cd").unwrap();
+        let first = AppendSymbol::from_qr(b"\x01\x04ab").unwrap();
+        assert_eq!(first.position, 0);
+        assert_eq!(first.total, 2);
+        assert_eq!(first.id, AppendId::Parity(0x04));
+
+        let mut collator = StructuredAppend::new(TIMEOUT);
+        assert_eq!(collator.push(second), Ok(None));
+        assert_eq!(collator.push(first), Ok(Some(b"abcd".to_vec())));
+    }
+
+    #[test]
+    fn qr_parity_mismatch() {
+        let mut collator = StructuredAppend::new(TIMEOUT);
+        let first = AppendSymbol::from_qr(b"\x01\x05ab").unwrap();
+        let second = AppendSymbol::from_qr(b"\x11\x05cd").unwrap();
+        assert_eq!(collator.push(first), Ok(None));
+        assert_eq!(
+            collator.push(second),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: 0x05,
+                found: 0x04
+            })
+        );
+    }
+
+    #[test]
+    fn data_matrix_set() {
+        let first = AppendSymbol::from_data_matrix(b"\x0f\x12\x34ab");
+        assert_eq!(
+            first,
+            Ok(AppendSymbol {
+                position: 0,
+                total: 2,
+                id: AppendId::FileId(0x1234),
+                data: b"ab",
+            })
+        );
+    }
+
+    #[test]
+    fn bad_header() {
+        // Position 2 of a single symbol
+        assert_eq!(
+            AppendSymbol::from_qr(b"\x20\x00ab"),
+            Err(ValidationError::InvalidCharacter {
+                position: 0,
+                byte: 0x20
+            })
+        );
+        assert_eq!(
+            AppendSymbol::from_data_matrix(b"\x0f\x12"),
+            Err(ValidationError::InvalidLength(2))
+        );
+    }
+}
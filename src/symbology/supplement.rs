@@ -0,0 +1,64 @@
+//! UPC/EAN supplements (`*Plus2`, `*Plus5`)
+//!
+//! The 2 or 5 digit add-on of magazines and books is transmitted right
+//! after the digits of the main code, without a separator.
+
+use crate::ContentType;
+
+/// Splits the supplement off the main code
+///
+/// Returns the whole of `content` and `None` for content types without a
+/// supplement, or if `content` is too short to carry one.
+pub fn split_supplement(
+    content_type: ContentType,
+    content: &[u8],
+) -> (&[u8], Option<&[u8]>) {
+    let len = match content_type {
+        ContentType::Ean13Plus2
+        | ContentType::Ean8Plus2
+        | ContentType::UpcAPlus2
+        | ContentType::UpcEPlus2
+        | ContentType::UpcE1Plus2 => 2,
+        ContentType::Ean13Plus5
+        | ContentType::Ean8Plus5
+        | ContentType::UpcAPlus5
+        | ContentType::UpcEPlus5
+        | ContentType::UpcE1Plus5 => 5,
+        _ => return (content, None),
+    };
+    if content.len() <= len {
+        return (content, None);
+    }
+
+    let (main, supplement) = content.split_at(content.len() - len);
+    (main, Some(supplement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supplement() {
+        assert_eq!(
+            split_supplement(ContentType::Ean13Plus2, b"400638133393112"),
+            (&b"4006381333931"[..], Some(&b"12"[..]))
+        );
+        assert_eq!(
+            split_supplement(ContentType::UpcAPlus5, b"01234567890551234"),
+            (&b"012345678905"[..], Some(&b"51234"[..]))
+        );
+    }
+
+    #[test]
+    fn no_supplement() {
+        assert_eq!(
+            split_supplement(ContentType::Ean13, b"4006381333931"),
+            (&b"4006381333931"[..], None)
+        );
+        assert_eq!(
+            split_supplement(ContentType::Ean8Plus5, b"12345"),
+            (&b"12345"[..], None)
+        );
+    }
+}
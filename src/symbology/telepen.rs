@@ -0,0 +1,129 @@
+//! Telepen
+//!
+//! Telepen encodes full 7 bit ASCII. Labels can additionally switch into a
+//! double density numeric mode, where each symbol character carries a pair
+//! of digits. A scanner set to ASCII output delivers those pairs as the raw
+//! symbol characters, which have to be mapped back to digits by the host.
+
+use super::{split_check, ValidationError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelepenMode {
+    /// Each byte is one ASCII character
+    Ascii,
+    /// Each byte carries two digits (`27 + nn`), or a single digit followed
+    /// by the `X` filler (`17 + n`)
+    Numeric,
+}
+
+/// Modulo 127 check character over the symbol characters in `data`
+pub fn telepen_check_char(data: &[u8]) -> u8 {
+    let sum: u32 = data.iter().copied().map(u32::from).sum();
+    ((127 - sum % 127) % 127) as u8
+}
+
+/// Validates a Telepen payload and interprets it according to `mode`
+///
+/// `content` is the data as delivered with the scanner's Telepen output set
+/// to ASCII. With `check_char` set, the last byte is the transmitted check
+/// character, which is verified and stripped.
+pub fn decode_telepen(
+    content: &[u8],
+    mode: TelepenMode,
+    check_char: bool,
+) -> Result<String, ValidationError> {
+    if content.is_empty() {
+        return Err(ValidationError::InvalidLength(0));
+    }
+    if let Some(position) = content.iter().position(|b| !b.is_ascii()) {
+        return Err(ValidationError::InvalidCharacter {
+            position,
+            byte: content[position],
+        });
+    }
+
+    let data = if check_char {
+        split_check(content, telepen_check_char)?
+    } else {
+        content
+    };
+
+    match mode {
+        TelepenMode::Ascii => {
+            Ok(data.iter().copied().map(char::from).collect())
+        }
+        TelepenMode::Numeric => {
+            let mut output = String::with_capacity(data.len() * 2);
+            for (position, byte) in data.iter().copied().enumerate() {
+                match byte {
+                    27..=126 => {
+                        let pair = byte - 27;
+                        output.push(char::from(b'0' + pair / 10));
+                        output.push(char::from(b'0' + pair % 10));
+                    }
+                    17..=26 => {
+                        output.push(char::from(b'0' + byte - 17));
+                        output.push('X');
+                    }
+                    _ => {
+                        return Err(ValidationError::InvalidCharacter {
+                            position,
+                            byte,
+                        })
+                    }
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_char() {
+        // 65 + 66 = 131, completed to 254
+        assert_eq!(telepen_check_char(b"AB"), b'{');
+        assert_eq!(telepen_check_char(b""), 0);
+    }
+
+    #[test]
+    fn ascii() {
+        assert_eq!(
+            decode_telepen(b"AB{", TelepenMode::Ascii, true),
+            Ok("AB".to_string())
+        );
+        assert_eq!(
+            decode_telepen(b"AB}", TelepenMode::Ascii, true),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'{',
+                found: b'}'
+            })
+        );
+        assert_eq!(
+            decode_telepen(b"A\xc4", TelepenMode::Ascii, false),
+            Err(ValidationError::InvalidCharacter {
+                position: 1,
+                byte: 0xc4
+            })
+        );
+    }
+
+    #[test]
+    fn numeric() {
+        // 27 + 12 and 17 + 5
+        assert_eq!(
+            decode_telepen(&[39, 22], TelepenMode::Numeric, false),
+            Ok("125X".to_string())
+        );
+        assert_eq!(
+            decode_telepen(&[39, 5], TelepenMode::Numeric, false),
+            Err(ValidationError::InvalidCharacter {
+                position: 1,
+                byte: 5
+            })
+        );
+    }
+}
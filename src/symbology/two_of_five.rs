@@ -0,0 +1,121 @@
+//! The numeric-only 2 of 5 family, and its Korean 3 of 5 relative
+
+use super::{
+    check_digits, digit_sum_check_digit, mod10_check_digit, split_check,
+    ValidationError,
+};
+
+/// Validates a Matrix 2 of 5 payload and returns its digits
+///
+/// With `check_digit` set, the last digit is treated as a transmitted
+/// modulo 10 check digit, verified and stripped from the result. Only
+/// enable this when the scanner is configured to transmit the check digit.
+pub fn validate_matrix2of5(
+    content: &[u8],
+    check_digit: bool,
+) -> Result<&str, ValidationError> {
+    if content.is_empty() {
+        return Err(ValidationError::InvalidLength(0));
+    }
+    check_digits(content)?;
+
+    let digits = if check_digit {
+        split_check(content, mod10_check_digit)?
+    } else {
+        content
+    };
+
+    // Only ASCII digits at this point
+    Ok(std::str::from_utf8(digits).unwrap())
+}
+
+/// Validates a Chinese 2 of 5 payload and returns its digits
+///
+/// Chinese 2 of 5 has no check digit.
+pub fn validate_chinese2of5(content: &[u8]) -> Result<&str, ValidationError> {
+    if content.is_empty() {
+        return Err(ValidationError::InvalidLength(0));
+    }
+    check_digits(content)?;
+
+    // Only ASCII digits at this point
+    Ok(std::str::from_utf8(content).unwrap())
+}
+
+/// Validates a Korean 3 of 5 payload and returns the 6 digit postal code
+///
+/// With `check_digit` set, a seventh digit completing the digit sum to a
+/// multiple of 10 is expected, verified and stripped.
+pub fn validate_korean3of5(
+    content: &[u8],
+    check_digit: bool,
+) -> Result<&str, ValidationError> {
+    check_digits(content)?;
+
+    let digits = if check_digit {
+        split_check(content, digit_sum_check_digit)?
+    } else {
+        content
+    };
+    if digits.len() != 6 {
+        return Err(ValidationError::InvalidLength(content.len()));
+    }
+
+    // Only ASCII digits at this point
+    Ok(std::str::from_utf8(digits).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn korean3of5() {
+        assert_eq!(validate_korean3of5(b"123456", false), Ok("123456"));
+        assert_eq!(validate_korean3of5(b"1234569", true), Ok("123456"));
+        assert_eq!(
+            validate_korean3of5(b"1234560", true),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'9',
+                found: b'0'
+            })
+        );
+        assert_eq!(
+            validate_korean3of5(b"12345", false),
+            Err(ValidationError::InvalidLength(5))
+        );
+        assert_eq!(
+            validate_korean3of5(b"12a456", false),
+            Err(ValidationError::InvalidCharacter {
+                position: 2,
+                byte: b'a'
+            })
+        );
+    }
+
+    #[test]
+    fn chinese2of5() {
+        assert_eq!(validate_chinese2of5(b"0123"), Ok("0123"));
+        assert_eq!(
+            validate_chinese2of5(b""),
+            Err(ValidationError::InvalidLength(0))
+        );
+    }
+
+    #[test]
+    fn matrix2of5() {
+        assert_eq!(validate_matrix2of5(b"1234", false), Ok("1234"));
+        assert_eq!(validate_matrix2of5(b"12348", true), Ok("1234"));
+        assert_eq!(
+            validate_matrix2of5(b"12340", true),
+            Err(ValidationError::CheckDigitMismatch {
+                expected: b'8',
+                found: b'0'
+            })
+        );
+        assert_eq!(
+            validate_matrix2of5(b"", false),
+            Err(ValidationError::InvalidLength(0))
+        );
+    }
+}
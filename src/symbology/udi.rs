@@ -0,0 +1,103 @@
+//! Unique Device Identifiers as parsed by the scanner (`UdiParsed`)
+//!
+//! In parsed UDI mode the scanner recognizes the UDI issuing agency format
+//! (GS1, HIBCC or ICCBBA) and transmits the content as labeled fields
+//! instead of the raw symbol data. Each field is a label, a `:` and the
+//! value. Fields are separated by a group separator (0x1d) or a line break
+//! (CR, LF or CR LF), depending on the scanner's settings.
+//!
+//! Firmware revisions differ in how they label the fields, so the common
+//! label spellings as well as the GS1 AIs are recognized.
+
+use super::ValidationError;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Udi<'a> {
+    /// Device identifier (GTIN, HIBC LIC/PCN or ISBT 128 PPIC)
+    pub di: Option<&'a str>,
+    pub lot: Option<&'a str>,
+    pub serial: Option<&'a str>,
+    pub expiry: Option<&'a str>,
+    pub manufactured: Option<&'a str>,
+    /// Fields with labels not covered above, in transmission order
+    pub other: Vec<(&'a str, &'a str)>,
+}
+
+/// Splits a parsed UDI payload into its labeled fields
+pub fn parse_udi(content: &[u8]) -> Result<Udi<'_>, ValidationError> {
+    let content = std::str::from_utf8(content).map_err(|e| {
+        ValidationError::InvalidCharacter {
+            position: e.valid_up_to(),
+            byte: content[e.valid_up_to()],
+        }
+    })?;
+
+    let mut udi = Udi::default();
+    let fields = content
+        .split(['\x1d', '\r', '\n'])
+        .filter(|field| !field.is_empty());
+
+    for field in fields {
+        let Some((label, value)) = field.split_once(':') else {
+            let position = field.as_ptr() as usize - content.as_ptr() as usize;
+            return Err(ValidationError::InvalidCharacter {
+                position,
+                byte: field.as_bytes()[0],
+            });
+        };
+        let value = value.trim();
+
+        let slot = match label.trim().to_ascii_uppercase().as_str() {
+            "DI" | "GTIN" | "UDI-DI" | "01" => &mut udi.di,
+            "LOT" | "BATCH" | "10" => &mut udi.lot,
+            "SN" | "SERIAL" | "21" => &mut udi.serial,
+            "EXP" | "EXPIRY" | "17" => &mut udi.expiry,
+            "MFG" | "PROD" | "11" => &mut udi.manufactured,
+            _ => {
+                udi.other.push((label.trim(), value));
+                continue;
+            }
+        };
+        *slot = Some(value);
+    }
+
+    Ok(udi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let udi = parse_udi(b"GTIN:00123\x1dlot: A1\r\n21:42\x1dXY:z").unwrap();
+        assert_eq!(
+            udi,
+            Udi {
+                di: Some("00123"),
+                lot: Some("A1"),
+                serial: Some("42"),
+                other: vec![("XY", "z")],
+                ..Udi::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unlabeled_field() {
+        assert_eq!(
+            parse_udi(b"GTIN:1\x1dnone"),
+            Err(ValidationError::InvalidCharacter {
+                position: 7,
+                byte: b'n'
+            })
+        );
+        assert_eq!(
+            parse_udi(b"LOT:\xff"),
+            Err(ValidationError::InvalidCharacter {
+                position: 4,
+                byte: 0xff
+            })
+        );
+    }
+}
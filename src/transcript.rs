@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use crate::{decode_with, SsiConfig};
+
+/// Which way a frame went over the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ScannerToHost,
+    HostToScanner,
+}
+
+/// Formats captured frames as an annotated transcript
+///
+/// Every frame gets a line with its direction and hex bytes, followed by
+/// what it decodes to. Meant for attaching a session to a bug report.
+pub fn transcript(frames: &[(Direction, Vec<u8>)]) -> String {
+    let config = SsiConfig::default();
+    let mut output = String::new();
+
+    for (direction, frame) in frames {
+        let arrow = match direction {
+            Direction::ScannerToHost => "scanner -> host",
+            Direction::HostToScanner => "host -> scanner",
+        };
+        // Writing to a String can't fail
+        write!(output, "{arrow}:").unwrap();
+        for byte in frame {
+            write!(output, " {byte:02x}").unwrap();
+        }
+        output.push('\n');
+
+        match decode_with(frame, &config) {
+            Ok(message) => {
+                writeln!(output, "    {message}").unwrap();
+
+                if let Some(scan) = message.scan_data(config.code_id) {
+                    match scan.symbology() {
+                        Some(content_type) => {
+                            write!(output, "    {content_type}").unwrap()
+                        }
+                        None => write!(
+                            output,
+                            "    Unknown type {:#04x}",
+                            scan.content_type
+                        )
+                        .unwrap(),
+                    }
+                    writeln!(
+                        output,
+                        ": '{}'",
+                        String::from_utf8_lossy(scan.content)
+                    )
+                    .unwrap();
+                }
+            }
+            Err(e) => writeln!(output, "    error: {e}").unwrap(),
+        }
+    }
+
+    output
+}
+
+/// Formats `bytes` as hex dump, 16 bytes per line, each line starting with
+/// the offset and ending with the printable ASCII characters
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut output = String::new();
+
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        write!(output, "{:04x} ", line * 16).unwrap();
+        for byte in chunk {
+            write!(output, " {byte:02x}").unwrap();
+        }
+        // Line up the ASCII column of a short last line
+        let padding = 3 * (16 - chunk.len()) + 2;
+        write!(output, "{:padding$}|", "").unwrap();
+        for &byte in chunk {
+            let char = match byte {
+                0x20..=0x7e => char::from(byte),
+                _ => '.',
+            };
+            output.push(char);
+        }
+        output.push_str("|\n");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::scan_enable;
+    use crate::{wrap, OpCode};
+
+    #[test]
+    fn annotated() {
+        let scan =
+            wrap(vec![OpCode::DecodeData.into(), 0x00, 0x00, 0x0b, b'4'])
+                .unwrap();
+        let frames = [
+            (Direction::HostToScanner, scan_enable()),
+            (Direction::ScannerToHost, scan),
+            (Direction::ScannerToHost, vec![0x04, 0xd0]),
+        ];
+
+        assert_eq!(
+            transcript(&frames),
+            "host -> scanner: 04 e9 04 00 ff 0f\n    \
+             SCAN_ENABLE from Host, 0 data bytes\n\
+             scanner -> host: 06 f3 00 00 0b 34 fe c8\n    \
+             DECODE_DATA from Scanner, 2 data bytes\n    \
+             EAN-13: '4'\n\
+             scanner -> host: 04 d0\n    \
+             error: invalid message length 2\n"
+        );
+    }
+}